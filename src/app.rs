@@ -1,14 +1,22 @@
 use leptos::prelude::*;
 use leptos_meta::{MetaTags, *};
 use leptos_router::{
-    components::{ParentRoute, Route, Router, Routes, A},
-    hooks::use_params,
+    components::{ParentRoute, ProtectedRoute, Route, Router, Routes, A},
+    hooks::{use_navigate, use_params, use_query_map},
     nested_router::Outlet,
     params::Params,
     path, MatchNestedRoutes, ParamSegment, SsrMode, StaticSegment,
 };
 
-use crate::sync_await::SyncAwait;
+use crate::{
+    auth::{
+        permitted, provide_auth, redirect_unpermitted, CsrfClient, CsrfField, LoginPage,
+        LogoutPage, Permission, UserPortlet, UserResource,
+    },
+    error::{AppError, ErrorPage},
+    events, query,
+    sync_await::SyncAwait,
+};
 
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
 pub struct Author {
@@ -22,54 +30,107 @@ pub struct Article {
     pub title: String,
 }
 
+/// A single page of items out of a listing.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// The number of this page, starting from 1.
+    pub number: usize,
+    /// The number of the final page.
+    pub last: usize,
+}
+
+impl<T> Page<T> {
+    /// Select the page with the given number out of all the items, with
+    /// the number clamped to the pages available.
+    pub fn new(items: Vec<T>, number: usize, size: usize) -> Self {
+        let size = size.max(1);
+        let last = items.len().div_ceil(size).max(1);
+        let number = number.clamp(1, last);
+        let items = items
+            .into_iter()
+            .skip((number - 1) * size)
+            .take(size)
+            .collect();
+        Self {
+            items,
+            number,
+            last,
+        }
+    }
+}
+
 #[cfg(feature = "ssr")]
 pub(super) mod server {
-    use super::{Article, Author};
-    use std::{collections::HashMap, sync::LazyLock};
-
-    impl From<(&'static str, &'static str)> for Author {
-        fn from((name, email): (&'static str, &'static str)) -> Self {
-            Author {
-                name: name.to_string(),
-                email: email.to_string(),
-            }
-        }
+    use crate::{
+        cache::Cache,
+        config::AppConfig,
+        error::AppError,
+        events::{Change, Events},
+        repository::Repository,
+    };
+    use leptos::prelude::*;
+
+    pub fn config() -> AppConfig {
+        use_context::<AppConfig>().unwrap_or_default()
     }
 
-    impl From<(&'static str, &'static str)> for Article {
-        fn from((author_name, title): (&'static str, &'static str)) -> Self {
-            Article {
-                author_name: author_name.to_string(),
-                title: title.to_string(),
-            }
-        }
+    pub async fn simulate_latency(config: &AppConfig) {
+        tokio::time::sleep(config.latency()).await;
     }
 
-    pub static TIMEOUT: u64 = 50;
+    pub fn repository() -> Result<Repository, AppError> {
+        use_context::<Repository>()
+            .ok_or_else(|| AppError::Internal("repository not provided".to_string()))
+    }
 
-    pub static AUTHORS: LazyLock<HashMap<&'static str, Author>> = LazyLock::new(|| {
-        HashMap::from([
-            ("albert", ("Albert", "albert.g@example.com").into()),
-            ("bethany", ("Bethany", "beth@example.com").into()),
-            ("carl", ("Carl", "c.smith@example.com").into()),
-            ("dorothy", ("Dorothy", "dorothy@example.com").into()),
-        ])
-    });
+    /// Without a cache provided, caching is disabled.
+    pub fn cache() -> Cache {
+        use_context::<Cache>().unwrap_or_default()
+    }
 
-    pub static ARTICLES: LazyLock<Vec<Article>> = LazyLock::new(|| {
-        vec![
-            ("dorothy", "The top twenty...").into(),
-            ("albert", "On the practical nature of...").into(),
-            ("bethany", "How to guide to...").into(),
-            ("dorothy", "The top ten...").into(),
-            ("albert", "Why a city's infrastructure...").into(),
-            ("bethany", "The ultimate guide to...").into(),
-            ("dorothy", "The top hundred...").into(),
-            ("carl", "A quick summary on...").into(),
-            ("dorothy", "The top thousand...").into(),
-            ("bethany", "Beware of...").into(),
-        ]
-    });
+    /// Drop the cached reads affected by the change and push it to the
+    /// open pages.
+    pub fn changed(change: Change) {
+        cache().invalidate(change.entity.reads());
+        if let Some(events) = use_context::<Events>() {
+            events.publish(change);
+        }
+    }
+
+    /// The articles by the author, or `NotFound` rather than none when
+    /// there is no such author.
+    pub fn articles_by_author(
+        repository: &Repository,
+        name: &str,
+    ) -> Result<Vec<(u32, super::Article)>, AppError> {
+        if repository.get_author(name)?.is_none() {
+            return Err(AppError::NotFound(format!("author {name}")));
+        }
+        Ok(repository.list_articles_by_author(name)?)
+    }
+
+    /// Run the body of a read server function, with its result cached by
+    /// the function name and arguments.
+    pub async fn read<T>(
+        function: &'static str,
+        args: &impl serde::Serialize,
+        read: impl FnOnce(&Repository, &AppConfig) -> Result<T, AppError>,
+    ) -> Result<T, AppError>
+    where
+        T: Clone + Send + Sync + 'static,
+    {
+        crate::metrics::server_fn(function, async move {
+            cache()
+                .get_or_fetch(function, args, || async move {
+                    let config = config();
+                    simulate_latency(&config).await;
+                    read(&repository()?, &config)
+                })
+                .await
+        })
+        .await
+    }
 }
 
 #[cfg(feature = "ssr")]
@@ -83,6 +144,8 @@ pub mod navigation {
     pub struct NavItem {
         pub href: String,
         pub text: String,
+        /// What the user must be permitted to do for the item to be shown.
+        pub permission: Option<Permission>,
     }
 
     #[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
@@ -105,20 +168,34 @@ pub mod navigation {
     impl IntoRender for NavItems {
         type Output = AnyView;
 
+        /// Items requiring a permission are only shown to users who have
+        /// it, rerendering whenever the user logs in or out.
         fn into_render(self) -> Self::Output {
+            let user = expect_context::<UserResource>();
+            let items = self.into_inner();
+            let links = move || {
+                let items = items.clone();
+                Suspend::new(async move {
+                    let user = user.await.ok().flatten();
+                    items
+                        .into_iter()
+                        .filter(|item| match (&item.permission, &user) {
+                            (None, _) => true,
+                            (Some(permission), Some(user)) => user.can(permission),
+                            (Some(_), None) => false,
+                        })
+                        .map(|NavItem { href, text, .. }| {
+                            view! {
+                                <A href=href>{text}</A>
+                            }
+                        })
+                        .collect_view()
+                })
+            };
             view! {
                 <section id="NavPortlet">
                     <heading>"Navigation"</heading>
-                    <nav>{
-                        self.into_inner()
-                            .into_iter()
-                            .map(|NavItem { href, text }| {
-                                view! {
-                                    <A href=href>{text}</A>
-                                }
-                            })
-                            .collect_view()
-                    }</nav>
+                    <nav><Suspense>{links}</Suspense></nav>
                 </section>
             }
             .into_any()
@@ -133,8 +210,54 @@ pub mod navigation {
 
 use navigation::*;
 
+pub mod seo {
+    use super::*;
+    use leptos_router::hooks::use_location;
+
+    /// The absolute URL of the current page, used as its canonical URL.
+    fn canonical_url() -> String {
+        let path = use_location().pathname.get_untracked();
+        #[cfg(feature = "ssr")]
+        let base = super::server::config().public_url;
+        #[cfg(not(feature = "ssr"))]
+        let base = window().location().origin().unwrap_or_default();
+        format!("{}{path}", base.trim_end_matches('/'))
+    }
+
+    /// The title and description of the current page, along with its
+    /// canonical URL and the OpenGraph and Twitter equivalents, which are
+    /// rendered into the `<head>` during SSR when placed in the view of a
+    /// resolved resource.
+    #[component]
+    pub fn PageMeta(
+        #[prop(into)] title: String,
+        #[prop(into)] description: String,
+        /// The OpenGraph type of the page.
+        #[prop(default = "website")]
+        kind: &'static str,
+    ) -> impl IntoView {
+        let url = canonical_url();
+        view! {
+            <Title text=title.clone()/>
+            <Meta name="description" content=description.clone()/>
+            <Link rel="canonical" href=url.clone()/>
+            <Meta property="og:type" content=kind/>
+            <Meta property="og:site_name" content="Reactive Portlets in Leptos"/>
+            <Meta property="og:title" content=title.clone()/>
+            <Meta property="og:description" content=description.clone()/>
+            <Meta property="og:url" content=url/>
+            <Meta name="twitter:card" content="summary"/>
+            <Meta name="twitter:title" content=title/>
+            <Meta name="twitter:description" content=description/>
+        }
+    }
+}
+
+use seo::PageMeta;
+
 pub fn shell(options: LeptosOptions) -> impl IntoView {
-    leptos::logging::log!(">>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>");
+    #[cfg(feature = "ssr")]
+    crate::server::security::set_content_security_policy();
     view! {
         <!DOCTYPE html>
         <html lang="en">
@@ -142,7 +265,8 @@ pub fn shell(options: LeptosOptions) -> impl IntoView {
                 <meta charset="utf-8"/>
                 <meta name="viewport" content="width=device-width, initial-scale=1"/>
                 <AutoReload options=options.clone()/>
-                <HydrationScripts options/>
+                <HydrationScripts options=options.clone()/>
+                <HashedStylesheet options id="leptos"/>
                 <MetaTags/>
             </head>
             <body>
@@ -153,48 +277,118 @@ pub fn shell(options: LeptosOptions) -> impl IntoView {
 }
 
 #[server]
-async fn list_authors() -> Result<Vec<(String, Author)>, ServerFnError> {
-    tokio::time::sleep(std::time::Duration::from_millis(TIMEOUT)).await;
-    Ok(AUTHORS
-        .iter()
-        .map(|(k, author)| (k.to_string(), author.clone()))
-        .collect::<Vec<_>>())
+async fn list_authors() -> Result<Vec<(String, Author)>, AppError> {
+    read("list_authors", &(), |repository, _| {
+        Ok(repository.list_authors()?)
+    })
+    .await
 }
 
 #[server]
-async fn get_author(name: String) -> Result<(String, Author), ServerFnError> {
-    tokio::time::sleep(std::time::Duration::from_millis(TIMEOUT)).await;
-    AUTHORS
-        .get_key_value(name.as_str())
-        .map(|(k, author)| (k.to_string(), author.clone()))
-        .ok_or_else(|| ServerFnError::ServerError(format!("no such author: {name}")))
+async fn get_author(name: String) -> Result<(String, Author), AppError> {
+    read("get_author", &name, |repository, _| {
+        repository
+            .get_author(&name)?
+            .ok_or(AppError::NotFound(format!("author {name}")))
+    })
+    .await
 }
 
 #[server]
-async fn list_articles() -> Result<Vec<(u32, Article)>, ServerFnError> {
-    tokio::time::sleep(std::time::Duration::from_millis(TIMEOUT)).await;
-    Ok((1..)
-        .zip(ARTICLES.iter())
-        .map(|(id, article)| (id, article.clone()))
-        .collect::<Vec<_>>())
+async fn list_articles(page: usize) -> Result<Page<(u32, Article)>, AppError> {
+    // the whole listing is cached, so that every page number shares it
+    let articles = read("list_articles", &(), |repository, _| {
+        Ok(repository.list_articles()?)
+    })
+    .await?;
+    Ok(Page::new(articles, page, config().page_size))
 }
 
 #[server]
-async fn list_articles_by_author(name: String) -> Result<Vec<(u32, Article)>, ServerFnError> {
-    tokio::time::sleep(std::time::Duration::from_millis(TIMEOUT)).await;
-    Ok((1..)
-        .zip(ARTICLES.iter())
-        .filter_map(|(id, article)| (article.author_name == name).then(|| (id, article.clone())))
-        .collect::<Vec<_>>())
+async fn list_articles_by_author(
+    name: String,
+    page: usize,
+) -> Result<Page<(u32, Article)>, AppError> {
+    let articles = read("list_articles_by_author", &name, |repository, _| {
+        articles_by_author(repository, &name)
+    })
+    .await?;
+    Ok(Page::new(articles, page, config().page_size))
 }
 
 #[server]
-async fn get_article(id: u32) -> Result<Article, ServerFnError> {
-    tokio::time::sleep(std::time::Duration::from_millis(TIMEOUT)).await;
-    id.checked_sub(1)
-        .map(|idx| ARTICLES.get(idx as usize).map(Article::clone))
-        .flatten()
-        .ok_or_else(|| ServerFnError::ServerError(format!("no such article: {id}")))
+async fn get_article(id: u32) -> Result<Article, AppError> {
+    read("get_article", &id, |repository, _| {
+        repository
+            .get_article(id)?
+            .ok_or(AppError::NotFound(format!("article {id}")))
+    })
+    .await
+}
+
+/// Add an article by the author, returning its id; the user must be
+/// permitted to write as the author.
+#[server(client = CsrfClient)]
+pub async fn create_article(author_name: String, title: String) -> Result<u32, AppError> {
+    crate::metrics::server_fn("create_article", async move {
+        simulate_latency(&config()).await;
+        crate::auth::authorize(&Permission::CreateArticle {
+            author_name: author_name.clone(),
+        })?;
+        let title = title.trim();
+        if title.is_empty() {
+            return Err(AppError::BadParam("title".to_string()));
+        }
+        let repository = repository()?;
+        if repository.get_author(&author_name)?.is_none() {
+            return Err(AppError::NotFound(format!("author {author_name}")));
+        }
+        let id = repository.create_article(Article {
+            author_name,
+            title: title.to_string(),
+        })?;
+        changed(events::Change::new(events::Entity::Article, id));
+        Ok(id)
+    })
+    .await
+}
+
+/// Change the title of the article; the user must be permitted to edit
+/// the articles of its author.
+#[server(client = CsrfClient)]
+pub async fn update_article(id: u32, title: String) -> Result<Article, AppError> {
+    crate::metrics::server_fn("update_article", async move {
+        simulate_latency(&config()).await;
+        let repository = repository()?;
+        let article = repository
+            .get_article(id)?
+            .ok_or(AppError::NotFound(format!("article {id}")))?;
+        crate::auth::authorize(&Permission::EditArticle {
+            author_name: article.author_name,
+        })?;
+        let title = title.trim();
+        if title.is_empty() {
+            return Err(AppError::BadParam("title".to_string()));
+        }
+        let article = repository
+            .update_article(id, title.to_string())?
+            .ok_or(AppError::NotFound(format!("article {id}")))?;
+        changed(events::Change::new(events::Entity::Article, id));
+        Ok(article)
+    })
+    .await
+}
+
+/// The page number requested via the `page` query parameter.
+fn use_page_number() -> Memo<usize> {
+    let query = use_query_map();
+    Memo::new(move |_| {
+        query
+            .read()
+            .get("page")
+            .and_then(|page| page.parse().ok())
+            .unwrap_or(1)
+    })
 }
 
 #[component]
@@ -203,12 +397,23 @@ pub fn App() -> impl IntoView {
     provide_meta_context();
     // provide_field_nav_portlet_context();
     NavPortletCtx::provide();
-    let fallback = || view! { "Page not found." }.into_view();
+    provide_auth();
+    let nav = expect_context::<ReadSignal<NavPortletCtx>>();
+    events::use_changes(move |change| {
+        tracing::debug!(
+            entity = change.entity.name(),
+            id = change.id,
+            "content changed"
+        );
+        query::invalidate(change.entity.reads());
+        nav.with_untracked(|ctx| ctx.refetch());
+    });
+    let fallback = || view! { <ErrorPage error=AppError::NoRoute/> };
 
     view! {
-        <Stylesheet id="leptos" href="/pkg/leptos_demo_portlet.css"/>
         <Title text="Reactive Portlets in Leptos"/>
         <Meta name="color-scheme" content="dark light"/>
+        <Link rel="alternate" type_="application/rss+xml" title="All articles" href="/feed.xml"/>
         <Router>
             <header>
                 <nav>
@@ -216,6 +421,7 @@ pub fn App() -> impl IntoView {
                     <A href="/author/">"Authors"</A>
                     <A href="/article/">"Articles"</A>
                 </nav>
+                <UserPortlet/>
             </header>
             <SyncAwait>
                 <main>
@@ -223,11 +429,23 @@ pub fn App() -> impl IntoView {
                         <NavPortlet/>
                     </aside>
                     <article>
-                        <Routes fallback>
-                            <Route path=path!("") view=HomePage/>
-                            <AuthorRoutes/>
-                            <ArticleRoutes/>
-                        </Routes>
+                        <ErrorBoundary fallback=|errors| {
+                            let error = errors
+                                .read()
+                                .iter()
+                                .next()
+                                .map(|(_, error)| AppError::from_error(error))
+                                .unwrap_or_else(|| AppError::Internal("unknown".to_string()));
+                            view! { <ErrorPage error/> }
+                        }>
+                            <Routes fallback>
+                                <Route path=path!("") view=HomePage/>
+                                <Route path=path!("login") view=LoginPage/>
+                                <Route path=path!("logout") view=LogoutPage/>
+                                <AuthorRoutes/>
+                                <ArticleRoutes/>
+                            </Routes>
+                        </ErrorBoundary>
                     </article>
                     // Uncomment this aside after should work also
                     // <aside>
@@ -242,7 +460,7 @@ pub fn App() -> impl IntoView {
 #[component]
 pub fn HomePage() -> impl IntoView {
     view! {
-        <Title text="Home Page"/>
+        <PageMeta title="Home Page" description="Authors and their articles, as reactive portlets."/>
         <h1>"Home Page"</h1>
         <ul>
             <li><a href="/author/">"Authors"</a></li>
@@ -259,6 +477,12 @@ pub fn AuthorRoutes() -> impl MatchNestedRoutes + Clone {
             <ParentRoute path=ParamSegment("name") view=AuthorTop>
                 <Route path=StaticSegment("/") view=AuthorOverview/>
                 <Route path=StaticSegment("articles") view=ArticleListing/>
+                <ProtectedRoute
+                    path=StaticSegment("new")
+                    view=ArticleNew
+                    condition=can_write_as_author
+                    redirect_path=|| redirect_unpermitted(author_path())
+                />
             </ParentRoute>
         </ParentRoute>
     }
@@ -267,10 +491,7 @@ pub fn AuthorRoutes() -> impl MatchNestedRoutes + Clone {
 
 #[component]
 pub fn AuthorContainer() -> impl IntoView {
-    provide_context(Resource::new_blocking(
-        move || (),
-        move |_| async move { list_authors().await },
-    ));
+    provide_context(query::resource("list_authors", || (), |_| list_authors()));
 
     view! {
         <h2>"<AuthorContainer/>"</h2>
@@ -280,18 +501,23 @@ pub fn AuthorContainer() -> impl IntoView {
 
 #[component]
 pub fn AuthorListing() -> impl IntoView {
-    let resource = expect_context::<Resource<Result<Vec<(String, Author)>, ServerFnError>>>();
+    let resource = expect_context::<Resource<Result<Vec<(String, Author)>, AppError>>>();
     let author_listing = move || {
         Suspend::new(async move {
             resource.await.map(|authors| {
-                authors
+                let description = format!("All {} authors.", authors.len());
+                let authors = authors
                     .into_iter()
                     .map(move |(id, author)| {
                         view! {
                             <li><a href=format!("/author/{id}/")>{author.name}</a></li>
                         }
                     })
-                    .collect_view()
+                    .collect_view();
+                view! {
+                    <PageMeta title="Authors" description/>
+                    {authors}
+                }
             })
         })
     };
@@ -312,55 +538,77 @@ struct AuthorTopParams {
 #[component]
 pub fn AuthorTop() -> impl IntoView {
     let params = use_params::<AuthorTopParams>();
-    provide_context(Resource::new_blocking(
-        move || params.get().map(|p| p.name),
-        move |name| async move {
+    provide_context(query::resource(
+        "get_author",
+        move || params.get().ok().and_then(|p| p.name),
+        |name| async move {
             match name {
-                Ok(Some(name)) => get_author(name).await,
-                _ => Err(ServerFnError::ServerError("parameter error".to_string())),
+                Some(name) => get_author(name).await,
+                None => Err(AppError::BadParam("author name".to_string())),
             }
         },
     ));
-    provide_context(Resource::new_blocking(
-        move || params.get().map(|p| p.name),
-        move |name| async move {
+    let page = use_page_number();
+    provide_context(query::resource(
+        "list_articles_by_author",
+        move || (params.get().ok().and_then(|p| p.name), page.get()),
+        |(name, page)| async move {
             match name {
-                Ok(Some(name)) => list_articles_by_author(name).await,
-                _ => Err(ServerFnError::ServerError("parameter error".to_string())),
+                Some(name) => list_articles_by_author(name, page).await,
+                None => Err(AppError::BadParam("author name".to_string())),
             }
         },
     ));
 
-    let resource = expect_context::<Resource<Result<Vec<(String, Author)>, ServerFnError>>>();
+    let resource = expect_context::<Resource<Result<Vec<(String, Author)>, AppError>>>();
     let ws = expect_context::<WriteSignal<NavPortletCtx>>();
     on_cleanup(move || {
         // cleanup in an effect somehow functions as a delay to prevent reposition
         // when unmounting one navigation and be replaced with another.
         Effect::new(move || {
-            leptos::logging::log!("Running cleanup of porlet for AuthorTop");
+            tracing::debug!("running cleanup of portlet for AuthorTop");
             ws.update(|c| c.clear());
         });
     });
     ws.update(move |c| {
-        leptos::logging::log!("Updating resource for AuthorTop");
-        c.set(ArcResource::new_blocking(
-            || (),
-            move |_| async move {
-                resource.await.map(|authors| {
-                    authors
-                        .into_iter()
-                        .map(move |(id, author)| NavItem {
+        tracing::debug!("updating portlet resource for AuthorTop");
+        c.set_derived(resource, |authors| {
+            authors
+                .into_iter()
+                .flat_map(|(id, author)| {
+                    [
+                        NavItem {
                             href: format!("/author/{id}/"),
-                            text: author.name.to_string(),
-                        })
-                        .collect::<Vec<_>>()
-                        .into()
+                            text: author.name.clone(),
+                            permission: None,
+                        },
+                        NavItem {
+                            href: format!("/author/{id}/new"),
+                            text: format!("New article by {}", author.name),
+                            permission: Some(Permission::CreateArticle { author_name: id }),
+                        },
+                    ]
                 })
-            },
-        ))
+                .collect::<Vec<_>>()
+                .into()
+        })
     });
 
+    let feed = move || {
+        params.get().ok().and_then(|p| p.name).map(|name| {
+            view! {
+                <Link
+                    rel="alternate"
+                    type_="application/rss+xml"
+                    title=format!("Articles by {name}")
+                    href=format!("/author/{name}/feed.xml")
+                />
+            }
+        })
+    };
+
     view! {
+        {feed}
         <h3>"<AuthorTop/>"</h3>
         <Outlet/>
     }
@@ -368,11 +616,13 @@ pub fn AuthorTop() -> impl IntoView {
 
 #[component]
 pub fn AuthorOverview() -> impl IntoView {
-    let resource = expect_context::<Resource<Result<(String, Author), ServerFnError>>>();
+    let resource = expect_context::<Resource<Result<(String, Author), AppError>>>();
     let author = move || {
         Suspend::new(async move {
             resource.await.map(move |(id, author)| {
+                let description = format!("{} and their articles.", author.name);
                 view! {
+                    <PageMeta title=author.name.clone() description kind="profile"/>
                     <dl>
                         <dt>"ID:"</dt>
                         <dd>{id}</dd>
@@ -395,6 +645,67 @@ pub fn AuthorOverview() -> impl IntoView {
     }
 }
 
+/// The page of the author of the route.
+fn author_path() -> String {
+    use_params::<AuthorTopParams>()
+        .get_untracked()
+        .ok()
+        .and_then(|p| p.name)
+        .map_or("/author/".to_string(), |name| format!("/author/{name}/"))
+}
+
+/// Whether the user may write articles as the author of the route, for
+/// the guard of the page adding one.
+fn can_write_as_author() -> Option<bool> {
+    let author_name = use_params::<AuthorTopParams>().get().ok()?.name?;
+    permitted(&Permission::CreateArticle { author_name })
+}
+
+#[component]
+pub fn ArticleNew() -> impl IntoView {
+    let params = use_params::<AuthorTopParams>();
+    let create = ServerAction::<CreateArticle>::new();
+    let navigate = use_navigate();
+    Effect::new(move || {
+        if let Some(Ok(id)) = create.value().get() {
+            query::invalidate(events::Entity::Article.reads());
+            navigate(&format!("/article/{id}/"), Default::default());
+        }
+    });
+    let error = move || {
+        create
+            .value()
+            .get()
+            .and_then(Result::err)
+            .map(|error| view! { <p class="error">{error.message()}</p> })
+    };
+    let form = move || {
+        params.get().ok().and_then(|p| p.name).map(|name| {
+            view! {
+                <PageMeta
+                    title=format!("New article by {name}")
+                    description=format!("Write a new article as {name}.")
+                />
+                <ActionForm action=create>
+                    <CsrfField/>
+                    <input type="hidden" name="author_name" value=name/>
+                    <label>
+                        "Title "
+                        <input type="text" name="title" required/>
+                    </label>
+                    <button type="submit">"Create"</button>
+                </ActionForm>
+            }
+        })
+    };
+
+    view! {
+        <h4>"<ArticleNew/>"</h4>
+        {form}
+        {error}
+    }
+}
+
 #[component]
 pub fn ArticleRoutes() -> impl MatchNestedRoutes + Clone {
     view! {
@@ -404,6 +715,12 @@ pub fn ArticleRoutes() -> impl MatchNestedRoutes + Clone {
                 <Route path=StaticSegment("/") view=ArticleView/>
                 <Route path=StaticSegment("comments") view=ArticleComments/>
                 <Route path=StaticSegment("history") view=ArticleHistory/>
+                <ProtectedRoute
+                    path=StaticSegment("edit")
+                    view=ArticleEdit
+                    condition=can_edit_article
+                    redirect_path=|| redirect_unpermitted(article_path())
+                />
             </ParentRoute>
         </ParentRoute>
     }
@@ -412,9 +729,11 @@ pub fn ArticleRoutes() -> impl MatchNestedRoutes + Clone {
 
 #[component]
 pub fn ArticleContainer() -> impl IntoView {
-    provide_context(Resource::new_blocking(
-        move || (),
-        move |_| async move { list_articles().await },
+    let page = use_page_number();
+    provide_context(query::resource(
+        "list_articles",
+        move || page.get(),
+        list_articles,
     ));
 
     view! {
@@ -425,27 +744,42 @@ pub fn ArticleContainer() -> impl IntoView {
 
 #[component]
 pub fn ArticleListing() -> impl IntoView {
-    let resource = expect_context::<Resource<Result<Vec<(u32, Article)>, ServerFnError>>>();
+    let resource = expect_context::<Resource<Result<Page<(u32, Article)>, AppError>>>();
     let article_listing = move || {
         Suspend::new(async move {
-            resource.await.map(|articles| {
-                articles
-                    .into_iter()
-                    .map(move |(id, article)| {
-                        view! {
-                            <li><a href=format!("/article/{id}/")>{article.title}</a></li>
-                        }
-                    })
-                    .collect_view()
+            resource.await.map(|page| {
+                let previous = (page.number > 1).then(|| {
+                    view! { <a href=format!("?page={}", page.number - 1)>"Previous"</a> }
+                });
+                let next = (page.number < page.last).then(|| {
+                    view! { <a href=format!("?page={}", page.number + 1)>"Next"</a> }
+                });
+                let description = format!("Articles, page {} of {}.", page.number, page.last);
+                view! {
+                    <PageMeta title="Articles" description/>
+                    <ul>{
+                        page.items
+                            .into_iter()
+                            .map(move |(id, article)| {
+                                view! {
+                                    <li><a href=format!("/article/{id}/")>{article.title}</a></li>
+                                }
+                            })
+                            .collect_view()
+                    }</ul>
+                    <nav class="pager">
+                        {previous}
+                        <span>{format!("Page {} of {}", page.number, page.last)}</span>
+                        {next}
+                    </nav>
+                }
             })
         })
     };
 
     view! {
         <h3>"<ArticleListing/>"</h3>
-        <ul>
-            <Suspense>{article_listing}</Suspense>
-        </ul>
+        <Suspense>{article_listing}</Suspense>
     }
 }
 
@@ -457,41 +791,49 @@ struct ArticleTopParams {
 #[component]
 pub fn ArticleTop() -> impl IntoView {
     let params = use_params::<ArticleTopParams>();
-    provide_context(Resource::new_blocking(
-        move || params.get().map(|p| p.id),
-        move |id| async move {
+    provide_context(query::resource(
+        "get_article",
+        move || params.get().ok().and_then(|p| p.id),
+        |id| async move {
             match id {
-                Ok(Some(id)) => get_article(id).await,
-                _ => Err(ServerFnError::ServerError("parameter error".to_string())),
+                Some(id) => get_article(id).await,
+                None => Err(AppError::BadParam("article id".to_string())),
             }
         },
     ));
 
-    let resource = expect_context::<Resource<Result<Vec<(u32, Article)>, ServerFnError>>>();
+    let resource = expect_context::<Resource<Result<Page<(u32, Article)>, AppError>>>();
     let ws = expect_context::<WriteSignal<NavPortletCtx>>();
     on_cleanup(move || {
         Effect::new(move || {
-            leptos::logging::log!("Running cleanup of porlet for ArticleTop");
+            tracing::debug!("running cleanup of portlet for ArticleTop");
             ws.update(|c| c.clear());
         });
     });
     ws.update(move |c| {
-        leptos::logging::log!("Updating resource for ArticleTop");
-        c.set(ArcResource::new_blocking(
-            || (),
-            move |_| async move {
-                resource.await.map(|articles| {
-                    articles
-                        .into_iter()
-                        .map(move |(id, article)| NavItem {
+        tracing::debug!("updating portlet resource for ArticleTop");
+        c.set_derived(resource, |page| {
+            page.items
+                .into_iter()
+                .flat_map(|(id, article)| {
+                    [
+                        NavItem {
                             href: format!("/article/{id}/"),
-                            text: article.title.to_string(),
-                        })
-                        .collect::<Vec<_>>()
-                        .into()
+                            text: article.title.clone(),
+                            permission: None,
+                        },
+                        NavItem {
+                            href: format!("/article/{id}/edit"),
+                            text: format!("Edit {}", article.title),
+                            permission: Some(Permission::EditArticle {
+                                author_name: article.author_name,
+                            }),
+                        },
+                    ]
                 })
-            },
-        ))
+                .collect::<Vec<_>>()
+                .into()
+        })
     });
     view! {
         <h3>"<ArticleTop/>"</h3>
@@ -501,12 +843,14 @@ pub fn ArticleTop() -> impl IntoView {
 
 #[component]
 pub fn ArticleView() -> impl IntoView {
-    let resource = expect_context::<Resource<Result<Article, ServerFnError>>>();
+    let resource = expect_context::<Resource<Result<Article, AppError>>>();
     let article = move || {
         Suspend::new(async move {
             resource.await.map(move |article| {
                 let author_href = format!("/author/{}/", article.author_name);
+                let description = format!("{} by {}.", article.title, article.author_name);
                 view! {
+                    <PageMeta title=article.title.clone() description kind="article"/>
                     <dl>
                         <dt>"Title:"</dt>
                         <dd>{article.title}</dd>
@@ -532,11 +876,14 @@ pub fn ArticleView() -> impl IntoView {
 
 #[component]
 pub fn ArticleComments() -> impl IntoView {
-    let resource = expect_context::<Resource<Result<Article, ServerFnError>>>();
+    let resource = expect_context::<Resource<Result<Article, AppError>>>();
     let article = move || {
         Suspend::new(async move {
             resource.await.map(move |article| {
+                let title = format!("Comments on {}", article.title);
+                let description = title.clone();
                 view! {
+                    <PageMeta title description/>
                     <h5>"Comments on article: "{article.title}</h5>
                     <p><A href="..">"Back to article"</A></p>
                 }
@@ -552,11 +899,14 @@ pub fn ArticleComments() -> impl IntoView {
 
 #[component]
 pub fn ArticleHistory() -> impl IntoView {
-    let resource = expect_context::<Resource<Result<Article, ServerFnError>>>();
+    let resource = expect_context::<Resource<Result<Article, AppError>>>();
     let article = move || {
         Suspend::new(async move {
             resource.await.map(move |article| {
+                let title = format!("History of {}", article.title);
+                let description = title.clone();
                 view! {
+                    <PageMeta title description/>
                     <h5>"History of "{article.title}</h5>
                     <p><A href="..">"Back to article"</A></p>
                 }
@@ -569,3 +919,74 @@ pub fn ArticleHistory() -> impl IntoView {
         <Suspense>{article}</Suspense>
     }
 }
+
+/// The page of the article of the route.
+fn article_path() -> String {
+    use_params::<ArticleTopParams>()
+        .get_untracked()
+        .ok()
+        .and_then(|p| p.id)
+        .map_or("/article/".to_string(), |id| format!("/article/{id}/"))
+}
+
+/// Whether the user may edit the article of the route, for the guard of
+/// its edit page.  An article that fails to load is let through for the
+/// page to show the error.
+fn can_edit_article() -> Option<bool> {
+    let resource = expect_context::<Resource<Result<Article, AppError>>>();
+    match resource.get()? {
+        Ok(article) => permitted(&Permission::EditArticle {
+            author_name: article.author_name,
+        }),
+        Err(_) => Some(true),
+    }
+}
+
+#[component]
+pub fn ArticleEdit() -> impl IntoView {
+    let resource = expect_context::<Resource<Result<Article, AppError>>>();
+    let params = use_params::<ArticleTopParams>();
+    let update = ServerAction::<UpdateArticle>::new();
+    let navigate = use_navigate();
+    Effect::new(move || {
+        if let Some(Ok(_)) = update.value().get() {
+            query::invalidate(events::Entity::Article.reads());
+            navigate(&article_path(), Default::default());
+        }
+    });
+    let error = move || {
+        update
+            .value()
+            .get()
+            .and_then(Result::err)
+            .map(|error| view! { <p class="error">{error.message()}</p> })
+    };
+    let article = move || {
+        Suspend::new(async move {
+            resource.await.map(move |article| {
+                let id = params.get_untracked().ok().and_then(|p| p.id);
+                let title = format!("Edit {}", article.title);
+                let description = title.clone();
+                view! {
+                    <PageMeta title description/>
+                    <ActionForm action=update>
+                        <CsrfField/>
+                        <input type="hidden" name="id" value=id/>
+                        <label>
+                            "Title "
+                            <input type="text" name="title" value=article.title required/>
+                        </label>
+                        <button type="submit">"Save"</button>
+                    </ActionForm>
+                    <p><A href="..">"Back to article"</A></p>
+                }
+            })
+        })
+    };
+
+    view! {
+        <h4>"<ArticleEdit/>"</h4>
+        <Suspense>{article}</Suspense>
+        {error}
+    }
+}
@@ -0,0 +1,589 @@
+//! Local accounts logging in with a username and password checked against
+//! the argon2 hashes in the repository.  A login is remembered by a cookie
+//! naming a session held in memory by the server, so all logins end when
+//! the server restarts.
+//!
+//! Each user has a `Role`, which the write server functions check for the
+//! `Permission` they need, and which the pages use to guard the routes and
+//! hide the links the user cannot use.
+//!
+//! Every session also has a CSRF token, which the forms of the write
+//! server functions carry in a hidden field added by `CsrfField`, and
+//! their requests sent once hydrated in a header added by `CsrfClient`,
+//! to be checked by the servers before the functions are called.
+
+use futures::{Sink, Stream};
+use leptos::{
+    prelude::*,
+    server_fn::{
+        client::{browser::BrowserClient, Client},
+        error::FromServerFnError,
+        request::browser::BrowserRequest,
+        response::browser::BrowserResponse,
+        Bytes,
+    },
+};
+use leptos_router::components::A;
+use std::{fmt, future::Future};
+
+use crate::{
+    app::seo::PageMeta,
+    error::AppError,
+    portlet::{render_portlet, PortletCtx},
+};
+
+/// The name of the cookie holding the session id.
+pub const COOKIE: &str = "session";
+
+/// The name of the form field carrying the CSRF token of the session.
+pub const CSRF_FIELD: &str = "csrf_token";
+
+/// The name of the header carrying the CSRF token of the session in the
+/// requests sent by `CsrfClient`.
+pub const CSRF_HEADER: &str = "x-csrf-token";
+
+/// What a user may do, with every role allowing all that the ones before
+/// it do.  Users write articles as the author with their username.
+#[derive(
+    serde::Serialize,
+    serde::Deserialize,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+)]
+#[cfg_attr(feature = "ssr", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// Reads the content, as anyone not logged in does
+    #[default]
+    Reader,
+    /// Writes and edits their own articles
+    Author,
+    /// Also edits the articles of every author
+    Editor,
+    /// Also writes articles as any author
+    Admin,
+}
+
+/// Something a user may or may not be allowed to do.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+pub enum Permission {
+    CreateArticle { author_name: String },
+    EditArticle { author_name: String },
+}
+
+impl fmt::Display for Permission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Permission::CreateArticle { author_name } => {
+                write!(f, "write articles as {author_name}")
+            }
+            Permission::EditArticle { author_name } => {
+                write!(f, "edit the articles of {author_name}")
+            }
+        }
+    }
+}
+
+impl Role {
+    /// Whether the user with the role and username has the permission.
+    pub fn allows(self, username: &str, permission: &Permission) -> bool {
+        let (Permission::CreateArticle { author_name } | Permission::EditArticle { author_name }) =
+            permission;
+        let own = author_name == username;
+        match (self, permission) {
+            (Role::Reader, _) => false,
+            (Role::Author, _) => own,
+            (Role::Editor, Permission::CreateArticle { .. }) => own,
+            (Role::Editor, Permission::EditArticle { .. }) => true,
+            (Role::Admin, _) => true,
+        }
+    }
+}
+
+/// The user the request was made by.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+pub struct UserInfo {
+    pub username: String,
+    pub role: Role,
+}
+
+impl UserInfo {
+    pub fn can(&self, permission: &Permission) -> bool {
+        self.role.allows(&self.username, permission)
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub use self::ssr::*;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use argon2::{
+        password_hash::{rand_core::OsRng, PasswordHash, SaltString},
+        Argon2, PasswordHasher, PasswordVerifier,
+    };
+    use leptos::prelude::*;
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    };
+
+    use super::{Permission, UserInfo, COOKIE};
+    use crate::error::AppError;
+
+    /// Hash the password with a new salt, in PHC string format.
+    pub fn hash_password(password: &str) -> Result<String, argon2::password_hash::Error> {
+        let salt = SaltString::generate(&mut OsRng);
+        Ok(Argon2::default()
+            .hash_password(password.as_bytes(), &salt)?
+            .to_string())
+    }
+
+    /// Whether the password matches the hash.  Without a hash, as for an
+    /// unknown user, a password is still verified against one so that the
+    /// time taken does not reveal whether the user exists.
+    pub fn verify_password(hash: Option<&str>, password: &str) -> bool {
+        // the hash of an unguessable password, only ever used for timing
+        const DECOY: &str =
+            "$argon2id$v=19$m=19456,t=2,p=1$x8S7fL4ndr7G6NLZRUEglA$C773r4/GuREJYK92rbl6DcmoIRZtUybJLzJ0svE8mv4";
+        let matches = PasswordHash::new(hash.unwrap_or(DECOY)).is_ok_and(|parsed| {
+            Argon2::default()
+                .verify_password(password.as_bytes(), &parsed)
+                .is_ok()
+        });
+        matches && hash.is_some()
+    }
+
+    struct Session {
+        username: String,
+        csrf_token: String,
+        expires: Instant,
+    }
+
+    /// 32 random bytes as hex, unguessable enough for ids and tokens.
+    fn random_token() -> String {
+        let mut bytes = [0u8; 32];
+        argon2::password_hash::rand_core::RngCore::fill_bytes(&mut OsRng, &mut bytes);
+        bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+
+    /// The sessions of the logged in users by session id.
+    #[derive(Clone, Default)]
+    pub struct Sessions {
+        sessions: Arc<Mutex<HashMap<String, Session>>>,
+        ttl: Duration,
+    }
+
+    impl Sessions {
+        /// Sessions lasting for the TTL since logging in.
+        pub fn new(ttl: Duration) -> Self {
+            Self {
+                sessions: Default::default(),
+                ttl,
+            }
+        }
+
+        pub fn ttl(&self) -> Duration {
+            self.ttl
+        }
+
+        /// Start a session for the user, returning its id.
+        pub fn create(&self, username: &str) -> String {
+            let id = random_token();
+            if let Ok(mut sessions) = self.sessions.lock() {
+                let now = Instant::now();
+                sessions.retain(|_, session| session.expires > now);
+                sessions.insert(
+                    id.clone(),
+                    Session {
+                        username: username.to_string(),
+                        csrf_token: random_token(),
+                        expires: now + self.ttl,
+                    },
+                );
+            }
+            id
+        }
+
+        /// The user of the session, unless it has expired.
+        pub fn get(&self, id: &str) -> Option<String> {
+            let sessions = self.sessions.lock().ok()?;
+            sessions
+                .get(id)
+                .filter(|session| session.expires > Instant::now())
+                .map(|session| session.username.clone())
+        }
+
+        /// The CSRF token of the session, unless it has expired.
+        pub fn csrf_token(&self, id: &str) -> Option<String> {
+            let sessions = self.sessions.lock().ok()?;
+            sessions
+                .get(id)
+                .filter(|session| session.expires > Instant::now())
+                .map(|session| session.csrf_token.clone())
+        }
+
+        pub fn remove(&self, id: &str) {
+            if let Ok(mut sessions) = self.sessions.lock() {
+                sessions.remove(id);
+            }
+        }
+    }
+
+    /// The value of the cookie out of a `Cookie` header.
+    pub fn find_cookie<'a>(header: &'a str, name: &str) -> Option<&'a str> {
+        header
+            .split(';')
+            .filter_map(|pair| pair.trim().split_once('='))
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value)
+    }
+
+    /// The value of the cookie sent with the request being handled.
+    pub fn request_cookie(name: &str) -> Option<String> {
+        #[cfg(feature = "axum")]
+        let headers = use_context::<axum::http::request::Parts>()?.headers;
+        #[cfg(feature = "actix")]
+        let headers = use_context::<leptos_actix::Request>()?.headers().clone();
+        #[cfg(any(feature = "axum", feature = "actix"))]
+        for value in headers.get_all("cookie") {
+            let Ok(value) = value.to_str() else {
+                continue;
+            };
+            if let Some(value) = find_cookie(value, name) {
+                return Some(value.to_string());
+            }
+        }
+        #[cfg(not(any(feature = "axum", feature = "actix")))]
+        let _ = name;
+        None
+    }
+
+    /// Have the response set the cookie, given as a `Set-Cookie` value.
+    pub fn set_cookie(cookie: &str) {
+        #[cfg(feature = "axum")]
+        if let (Some(response), Ok(value)) = (
+            use_context::<leptos_axum::ResponseOptions>(),
+            axum::http::HeaderValue::from_str(cookie),
+        ) {
+            response.append_header(axum::http::header::SET_COOKIE, value);
+        }
+        #[cfg(feature = "actix")]
+        if let (Some(response), Ok(value)) = (
+            use_context::<leptos_actix::ResponseOptions>(),
+            actix_web::http::header::HeaderValue::from_str(cookie),
+        ) {
+            response.append_header(actix_web::http::header::SET_COOKIE, value);
+        }
+        #[cfg(not(any(feature = "axum", feature = "actix")))]
+        let _ = cookie;
+    }
+
+    /// Redirect the browser once the server function completes.
+    pub fn redirect(path: &str) {
+        #[cfg(feature = "axum")]
+        leptos_axum::redirect(path);
+        #[cfg(feature = "actix")]
+        leptos_actix::redirect(path);
+        #[cfg(not(any(feature = "axum", feature = "actix")))]
+        let _ = path;
+    }
+
+    /// The `Set-Cookie` value for the session, marked `Secure` when the
+    /// site is served over HTTPS; an empty id with no TTL removes it.
+    pub fn session_cookie(id: &str, ttl: Duration, secure: bool) -> String {
+        let secure = if secure { "; Secure" } else { "" };
+        format!(
+            "{COOKIE}={id}; Path=/; HttpOnly; SameSite=Lax; Max-Age={}{secure}",
+            ttl.as_secs()
+        )
+    }
+
+    pub fn sessions() -> Sessions {
+        use_context::<Sessions>().unwrap_or_default()
+    }
+
+    /// The username of the session of the request, if there is one.
+    pub fn session_user() -> Option<String> {
+        sessions().get(&request_cookie(COOKIE)?)
+    }
+
+    /// The user of the session of the request, with the role they have
+    /// now rather than when they logged in.
+    pub fn request_user() -> Result<Option<UserInfo>, AppError> {
+        let Some(username) = session_user() else {
+            return Ok(None);
+        };
+        let user = crate::app::server::repository()?.get_user(&username)?;
+        Ok(user.map(|user| UserInfo {
+            username,
+            role: user.role,
+        }))
+    }
+
+    /// The user of the request, provided they have the permission.
+    pub fn authorize(permission: &Permission) -> Result<UserInfo, AppError> {
+        let user = request_user()?.ok_or(AppError::Unauthorized)?;
+        if !user.can(permission) {
+            tracing::info!(username = user.username, %permission, "permission denied");
+            return Err(AppError::Forbidden(permission.to_string()));
+        }
+        Ok(user)
+    }
+}
+
+/// The user logged in with the request, if any.
+#[server]
+pub async fn current_user() -> Result<Option<UserInfo>, AppError> {
+    crate::metrics::server_fn("current_user", async move { request_user() }).await
+}
+
+/// Start a session for the user with the password, redirecting home.
+#[server]
+pub async fn login(username: String, password: String) -> Result<(), AppError> {
+    use crate::app::server::{config, repository};
+
+    crate::metrics::server_fn("login", async move {
+        let user = repository()?.get_user(&username)?;
+        let hash = user.as_ref().map(|user| user.password_hash.as_str());
+        if !verify_password(hash, &password) {
+            tracing::info!(username, "login failed");
+            return Err(AppError::BadParam("username or password".to_string()));
+        }
+        let sessions = sessions();
+        let id = sessions.create(&username);
+        let secure = config().public_url.starts_with("https://");
+        set_cookie(&session_cookie(&id, sessions.ttl(), secure));
+        tracing::info!(username, "logged in");
+        redirect("/");
+        Ok(())
+    })
+    .await
+}
+
+/// The CSRF token of the session of the request, if any.
+#[server]
+pub async fn csrf_token() -> Result<Option<String>, AppError> {
+    crate::metrics::server_fn("csrf_token", async move {
+        Ok(request_cookie(COOKIE).and_then(|id| sessions().csrf_token(&id)))
+    })
+    .await
+}
+
+/// End the session of the request, redirecting home.
+#[server(client = CsrfClient)]
+pub async fn logout() -> Result<(), AppError> {
+    use crate::app::server::config;
+
+    crate::metrics::server_fn("logout", async move {
+        if let Some(id) = request_cookie(COOKIE) {
+            sessions().remove(&id);
+        }
+        let secure = config().public_url.starts_with("https://");
+        set_cookie(&session_cookie("", std::time::Duration::ZERO, secure));
+        redirect("/");
+        Ok(())
+    })
+    .await
+}
+
+/// What the user portlet shows: the user logged in, if any.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+pub struct UserStatus(Option<UserInfo>);
+
+pub type UserPortletCtx = PortletCtx<UserStatus>;
+
+impl IntoRender for UserStatus {
+    type Output = AnyView;
+
+    fn into_render(self) -> Self::Output {
+        match self.0 {
+            Some(UserInfo { username, .. }) => view! {
+                <section id="UserPortlet">
+                    <span class="username">{username}</span>
+                    <A href="/logout">"Log out"</A>
+                </section>
+            }
+            .into_any(),
+            None => view! {
+                <section id="UserPortlet">
+                    <A href="/login">"Log in"</A>
+                </section>
+            }
+            .into_any(),
+        }
+    }
+}
+
+/// The user logged in, as provided by `provide_auth`.
+pub type UserResource = Resource<Result<Option<UserInfo>, AppError>>;
+
+/// The CSRF token of the session, as provided by `provide_auth`.
+pub type CsrfResource = Resource<Result<Option<String>, AppError>>;
+
+/// Provide the login and logout actions along with the current user and
+/// CSRF token, refetched whenever either completes, and the user portlet
+/// showing the user.
+pub fn provide_auth() {
+    let login = ServerAction::<Login>::new();
+    let logout = ServerAction::<Logout>::new();
+    let versions = move || (login.version().get(), logout.version().get());
+    let user: UserResource = Resource::new_blocking(versions, |_| current_user());
+    let csrf: CsrfResource = Resource::new_blocking(versions, |_| csrf_token());
+    provide_context(login);
+    provide_context(logout);
+    provide_context(user);
+    provide_context(csrf);
+    UserPortletCtx::provide();
+    expect_context::<WriteSignal<UserPortletCtx>>().update(|ctx| ctx.set_derived(user, UserStatus));
+}
+
+/// Whether the user logged in has the permission, which is `None` while
+/// the user is loading; such as for the `condition` of a `ProtectedRoute`.
+/// Failing to get the user counts as being logged out.
+pub fn permitted(permission: &Permission) -> Option<bool> {
+    let user = expect_context::<UserResource>().get()?.ok().flatten();
+    Some(user.is_some_and(|user| user.can(permission)))
+}
+
+/// Where to send users away from a page they may not use: to log in, or
+/// if they already are, to the given path.
+pub fn redirect_unpermitted(path: String) -> String {
+    let user = expect_context::<UserResource>()
+        .get_untracked()
+        .and_then(Result::ok)
+        .flatten();
+    match user {
+        Some(_) => path,
+        None => "/login".to_string(),
+    }
+}
+
+/// The hidden field carrying the CSRF token of the session, which every
+/// form of a server function using `CsrfClient` must have.  Without a
+/// session the token is empty, which the server lets through as there is
+/// no login to abuse.
+#[component]
+pub fn CsrfField() -> impl IntoView {
+    let csrf = expect_context::<CsrfResource>();
+    let field = move || {
+        Suspend::new(async move {
+            let token = csrf.await.ok().flatten().unwrap_or_default();
+            view! { <input type="hidden" name=CSRF_FIELD value=token/> }
+        })
+    };
+
+    view! { <Suspense>{field}</Suspense> }
+}
+
+/// The client of the server functions protected by the CSRF token: the
+/// browser's, sending the token of the `CsrfField` on the page in the
+/// `CSRF_HEADER` of the request, as a hydrated `ActionForm` sends only
+/// the arguments of the server function and not the hidden field.
+pub struct CsrfClient;
+
+impl<E, IS, OS> Client<E, IS, OS> for CsrfClient
+where
+    E: FromServerFnError,
+    IS: FromServerFnError,
+    OS: FromServerFnError,
+{
+    type Request = BrowserRequest;
+    type Response = BrowserResponse;
+
+    fn send(req: BrowserRequest) -> impl Future<Output = Result<BrowserResponse, E>> + Send {
+        if let Some(token) = page_csrf_token() {
+            req.headers().set(CSRF_HEADER, &token);
+        }
+        <BrowserClient as Client<E, IS, OS>>::send(req)
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn open_websocket(
+        path: &str,
+    ) -> impl Future<
+        Output = Result<
+            (
+                impl Stream<Item = Result<Bytes, Bytes>> + Send + 'static,
+                impl Sink<Bytes> + Send + 'static,
+            ),
+            E,
+        >,
+    > + Send {
+        <BrowserClient as Client<E, IS, OS>>::open_websocket(path)
+    }
+
+    fn spawn(future: impl Future<Output = ()> + Send + 'static) {
+        <BrowserClient as Client<E, IS, OS>>::spawn(future)
+    }
+}
+
+/// The token in the `CsrfField` of the page, if it has one.
+#[cfg(feature = "hydrate")]
+fn page_csrf_token() -> Option<String> {
+    document()
+        .query_selector(&format!("input[name={CSRF_FIELD}]"))
+        .ok()??
+        .get_attribute("value")
+}
+
+/// Only the browser sends requests through the client.
+#[cfg(not(feature = "hydrate"))]
+fn page_csrf_token() -> Option<String> {
+    None
+}
+
+#[component]
+pub fn UserPortlet() -> impl IntoView {
+    render_portlet::<UserStatus>()
+}
+
+#[component]
+pub fn LoginPage() -> impl IntoView {
+    let login = expect_context::<ServerAction<Login>>();
+    let error = move || {
+        login
+            .value()
+            .get()
+            .and_then(Result::err)
+            .map(|error| view! { <p class="error">{error.message()}</p> })
+    };
+
+    view! {
+        <PageMeta title="Log in" description="Log in to the portlet demo."/>
+        <h2>"Log in"</h2>
+        <ActionForm action=login>
+            <label>
+                "Username "
+                <input type="text" name="username" autocomplete="username" required/>
+            </label>
+            <label>
+                "Password "
+                <input type="password" name="password" autocomplete="current-password" required/>
+            </label>
+            <button type="submit">"Log in"</button>
+        </ActionForm>
+        {error}
+    }
+}
+
+#[component]
+pub fn LogoutPage() -> impl IntoView {
+    let logout = expect_context::<ServerAction<Logout>>();
+
+    view! {
+        <PageMeta title="Log out" description="Log out of the portlet demo."/>
+        <h2>"Log out"</h2>
+        <ActionForm action=logout>
+            <CsrfField/>
+            <button type="submit">"Log out"</button>
+        </ActionForm>
+    }
+}
@@ -0,0 +1,143 @@
+//! A cache of the results of the read server functions, keyed by the
+//! function and its serialized arguments.  Entries expire after the
+//! configured TTL and are invalidated explicitly by the server functions
+//! writing to the repository; expired entries are swept on insert and no
+//! more than `MAX_ENTRIES` are kept.
+
+use std::{
+    any::Any,
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::metrics;
+
+/// The most entries kept, beyond which the one expiring first is dropped.
+pub const MAX_ENTRIES: usize = 1024;
+
+type Key = (&'static str, String);
+
+struct Entry {
+    value: Arc<dyn Any + Send + Sync>,
+    expires: Instant,
+}
+
+#[derive(Default)]
+struct Entries {
+    entries: HashMap<Key, Entry>,
+    /// Bumped on every invalidation, so that a result fetched before it
+    /// is not cached after it.
+    generation: u64,
+}
+
+#[derive(Clone, Default)]
+pub struct Cache {
+    entries: Arc<Mutex<Entries>>,
+    ttl: Duration,
+}
+
+impl Cache {
+    /// A cache keeping entries for the TTL; a zero TTL disables caching.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            entries: Default::default(),
+            ttl,
+        }
+    }
+
+    fn get<T: Clone + 'static>(&self, key: &Key) -> Option<T> {
+        let entries = &mut self.entries.lock().ok()?.entries;
+        match entries.get(key) {
+            Some(entry) if entry.expires > Instant::now() => entry.value.downcast_ref().cloned(),
+            Some(_) => {
+                entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn generation(&self) -> Option<u64> {
+        Some(self.entries.lock().ok()?.generation)
+    }
+
+    /// Cache the value unless the entries were invalidated since the
+    /// generation it was fetched in.
+    fn insert<T: Send + Sync + 'static>(&self, key: Key, value: T, generation: u64) {
+        let Ok(mut entries) = self.entries.lock() else {
+            return;
+        };
+        if entries.generation != generation {
+            return;
+        }
+        let now = Instant::now();
+        let entries = &mut entries.entries;
+        if !entries.contains_key(&key) && entries.len() >= MAX_ENTRIES {
+            entries.retain(|_, entry| entry.expires > now);
+            if entries.len() >= MAX_ENTRIES {
+                let first = entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.expires)
+                    .map(|(key, _)| key.clone());
+                if let Some(first) = first {
+                    entries.remove(&first);
+                }
+            }
+        }
+        entries.insert(
+            key,
+            Entry {
+                value: Arc::new(value),
+                expires: now + self.ttl,
+            },
+        );
+    }
+
+    /// The cached result of the function for the arguments, or else the
+    /// result of `fetch`, which is cached when successful and when the
+    /// arguments serialize.
+    pub async fn get_or_fetch<T, E, F>(
+        &self,
+        function: &'static str,
+        args: &impl serde::Serialize,
+        fetch: impl FnOnce() -> F,
+    ) -> Result<T, E>
+    where
+        T: Clone + Send + Sync + 'static,
+        F: Future<Output = Result<T, E>>,
+    {
+        if self.ttl.is_zero() {
+            return fetch().await;
+        }
+        let (Ok(args), Some(generation)) = (serde_json::to_string(args), self.generation()) else {
+            return fetch().await;
+        };
+        let key = (function, args);
+        if let Some(value) = self.get(&key) {
+            tracing::trace!(function, args = key.1, "cache hit");
+            metrics::record_cache_lookup(function, true);
+            return Ok(value);
+        }
+        tracing::trace!(function, args = key.1, "cache miss");
+        metrics::record_cache_lookup(function, false);
+        let value = fetch().await?;
+        self.insert(key, value.clone(), generation);
+        Ok(value)
+    }
+
+    /// Drop the cached results of the functions for all arguments.
+    pub fn invalidate(&self, functions: &[&'static str]) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.generation += 1;
+            entries
+                .entries
+                .retain(|(function, _), _| !functions.contains(function));
+        }
+        for function in functions {
+            tracing::debug!(function, "cache invalidated");
+            metrics::record_cache_invalidation(function);
+        }
+    }
+}
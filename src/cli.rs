@@ -0,0 +1,199 @@
+//! The command line interface of the server binary, shared by the axum
+//! and actix builds; only `serve` depends on the integration in use.
+
+use clap::{Parser, Subcommand};
+use leptos::{config::errors::LeptosConfigError, prelude::get_configuration};
+use std::{fmt, io::Write, path::PathBuf};
+
+use crate::{
+    auth::{self, Role},
+    config::{AppConfig, ConfigArgs, StorageBackend},
+    repository::{self, Content, Repository, RepositoryError, SCHEMA_VERSION},
+    server::{self, AppState},
+};
+
+/// Serve the portlet demo, or manage the content it serves.
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+pub struct Cli {
+    #[command(flatten)]
+    pub config: ConfigArgs,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Serve the app (the default when no command is given)
+    Serve,
+    #[command(flatten)]
+    Task(Task),
+}
+
+/// The commands that run to completion without starting the server.
+#[derive(Subcommand, Debug)]
+pub enum Task {
+    /// Upgrade the stored content to the current schema version
+    Migrate,
+    /// Replace the stored content with the demo content or a fixture
+    Seed {
+        /// A JSON fixture, in the format written by `export`
+        #[arg(long)]
+        fixture: Option<PathBuf>,
+        /// Overwrite existing content
+        #[arg(long)]
+        force: bool,
+    },
+    /// Write all the content as JSON
+    Export {
+        /// The file to write to instead of stdout
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Set the password of a user, read from stdin, adding the user if
+    /// there is none with the username
+    SetPassword {
+        /// The name the user logs in with
+        username: String,
+    },
+    /// Set the role of an existing user
+    SetRole {
+        /// The name the user logs in with
+        username: String,
+        /// The role to give the user
+        #[arg(value_enum)]
+        role: Role,
+    },
+    /// Print the routes of the app along with their SSR modes
+    Routes,
+    /// Render every page as static HTML, along with the site assets
+    ExportStatic {
+        /// The directory to write the site to
+        #[arg(long, short, default_value = "target/static")]
+        output: PathBuf,
+    },
+}
+
+/// Failures that prevent a task from completing.
+#[derive(thiserror::Error)]
+pub enum CliError {
+    #[error("failed to load configuration: {0}")]
+    Config(#[from] LeptosConfigError),
+    #[error("repository error: {0}")]
+    Repository(#[from] RepositoryError),
+    #[error("failed to read input: {0}")]
+    Input(#[source] std::io::Error),
+    #[error("failed to write output: {0}")]
+    Output(#[source] std::io::Error),
+    #[error("no password given on standard input")]
+    NoPassword,
+    #[error("there is no user {0}")]
+    UnknownUser(String),
+    #[error("failed to hash password: {0}")]
+    Password(#[source] argon2::password_hash::Error),
+    #[error("failed to access {}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("rendering {page} responded with status {status}")]
+    Render { page: String, status: u16 },
+}
+
+// As for `ServerError`, make `Debug` readable.
+impl fmt::Debug for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl Task {
+    pub async fn run(self, config: AppConfig) -> Result<(), CliError> {
+        let storage = &config.storage;
+        match self {
+            Task::Migrate => match storage.backend {
+                StorageBackend::Memory => {
+                    println!("the memory backend has nothing to migrate");
+                }
+                StorageBackend::Json => {
+                    let from = repository::migrate(&storage.path)?;
+                    if from < SCHEMA_VERSION {
+                        println!(
+                            "migrated {} from version {from} to {SCHEMA_VERSION}",
+                            storage.path.display()
+                        );
+                    } else {
+                        println!(
+                            "{} is already at version {SCHEMA_VERSION}",
+                            storage.path.display()
+                        );
+                    }
+                }
+            },
+            Task::Seed { fixture, force } => {
+                if storage.backend != StorageBackend::Json {
+                    return Err(RepositoryError::NotPersistent.into());
+                }
+                let content = match &fixture {
+                    Some(path) => Content::load_migrated(path)?,
+                    None => Content::demo(),
+                };
+                repository::seed(&storage.path, &content, force)?;
+                println!(
+                    "seeded {} with {} authors and {} articles",
+                    storage.path.display(),
+                    content.authors.len(),
+                    content.articles.len()
+                );
+            }
+            Task::Export { output } => {
+                let json = Repository::open(storage)?.snapshot()?.to_json();
+                match output {
+                    Some(path) => std::fs::write(path, json + "\n"),
+                    None => writeln!(std::io::stdout(), "{json}"),
+                }
+                .map_err(CliError::Output)?;
+            }
+            Task::SetPassword { username } => {
+                if storage.backend != StorageBackend::Json {
+                    return Err(RepositoryError::NotPersistent.into());
+                }
+                let mut password = String::new();
+                std::io::stdin()
+                    .read_line(&mut password)
+                    .map_err(CliError::Input)?;
+                let password = password.trim_end_matches(['\r', '\n']);
+                if password.is_empty() {
+                    return Err(CliError::NoPassword);
+                }
+                let hash = auth::hash_password(password).map_err(CliError::Password)?;
+                Repository::open(storage)?.set_password(&username, hash)?;
+                println!("set the password of {username}");
+            }
+            Task::SetRole { username, role } => {
+                if storage.backend != StorageBackend::Json {
+                    return Err(RepositoryError::NotPersistent.into());
+                }
+                if !Repository::open(storage)?.set_role(&username, role)? {
+                    return Err(CliError::UnknownUser(username));
+                }
+                println!("set the role of {username} to {role:?}");
+            }
+            Task::Routes => {
+                for route in server::route_list() {
+                    println!("{route}");
+                }
+            }
+            Task::ExportStatic { output } => {
+                let state = AppState::new(
+                    get_configuration(None)?.leptos_options,
+                    config.clone(),
+                    Repository::open(storage)?,
+                );
+                let pages = server::static_site::export(state, &output).await?;
+                println!("exported {pages} pages to {}", output.display());
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,354 @@
+use figment::{
+    providers::{Env, Format, Serialized, Toml},
+    Figment,
+};
+use serde::{Deserialize, Serialize};
+use std::{net::IpAddr, path::PathBuf, time::Duration};
+
+/// The configuration file read when `--config` is not specified; it is
+/// fine for this file to not exist.
+pub const DEFAULT_CONFIG_FILE: &str = "portlet.toml";
+
+/// Prefix for environment variables overriding the configuration file,
+/// with `__` separating nested keys, e.g. `PORTLET_STORAGE__BACKEND`.
+pub const ENV_PREFIX: &str = "PORTLET_";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    /// Keep the demo content in memory.
+    #[default]
+    Memory,
+    /// Keep the content in a JSON file at the storage path.
+    Json,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct StorageConfig {
+    pub backend: StorageBackend,
+    pub path: PathBuf,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            backend: StorageBackend::default(),
+            path: PathBuf::from("content.json"),
+        }
+    }
+}
+
+/// Limits on the requests to the server functions, counted per client IP
+/// and per session.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LimitsConfig {
+    /// Calls a minute allowed to the server functions reading content;
+    /// 0 disables the limit.
+    pub reads_per_minute: u32,
+    /// Calls a minute allowed to the server functions writing content
+    /// or logging in and out; 0 disables the limit.
+    pub writes_per_minute: u32,
+    /// The largest request body accepted by a server function, in bytes.
+    pub max_body_bytes: usize,
+    /// The reverse proxies whose `X-Forwarded-For` tells the IP of the
+    /// client connected to them.
+    pub trusted_proxies: Vec<IpAddr>,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            reads_per_minute: 120,
+            writes_per_minute: 10,
+            max_body_bytes: 64 * 1024,
+            trusted_proxies: Vec::new(),
+        }
+    }
+}
+
+/// The security headers sent by both servers.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SecurityConfig {
+    /// The `Content-Security-Policy` of the pages, in which `{nonce}` is
+    /// replaced by the nonce of the inline scripts of the response; empty
+    /// for none.
+    pub content_security_policy: String,
+    /// The `max-age` of the `Strict-Transport-Security` header, in
+    /// seconds; 0 for no header.
+    pub hsts_max_age_secs: u64,
+    /// The `Referrer-Policy` of every response.
+    pub referrer_policy: String,
+}
+
+impl Default for SecurityConfig {
+    fn default() -> Self {
+        Self {
+            content_security_policy: "default-src 'self'; \
+                script-src 'self' 'nonce-{nonce}' 'wasm-unsafe-eval'; \
+                style-src 'self'; img-src 'self' data:; object-src 'none'; \
+                base-uri 'self'; form-action 'self'; frame-ancestors 'none'"
+                .to_string(),
+            hsts_max_age_secs: 31_536_000,
+            referrer_policy: "strict-origin-when-cross-origin".to_string(),
+        }
+    }
+}
+
+/// How long browsers may cache the responses of both servers; hashed
+/// assets, whose names change with their content, are always cached for
+/// a year.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct HttpCacheConfig {
+    /// The `max-age` of the pages, which browsers cache privately as they
+    /// differ by login, in seconds; 0 to have them revalidated every time.
+    pub html_max_age_secs: u64,
+    /// The `max-age` of the assets without a hash in their names, in
+    /// seconds.
+    pub asset_max_age_secs: u64,
+}
+
+impl Default for HttpCacheConfig {
+    fn default() -> Self {
+        Self {
+            html_max_age_secs: 0,
+            asset_max_age_secs: 3600,
+        }
+    }
+}
+
+/// Application specific configuration, separate from the `LeptosOptions`
+/// and provided as context to the app and server functions.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AppConfig {
+    pub storage: StorageConfig,
+    pub limits: LimitsConfig,
+    pub security: SecurityConfig,
+    pub http_cache: HttpCacheConfig,
+    /// Simulated latency added to every server function, in milliseconds.
+    pub latency_ms: u64,
+    /// The longest a portlet will wait for the rest of the page to render
+    /// before rendering itself, in milliseconds.
+    pub waiter_timeout_ms: u64,
+    /// The number of items shown per page in listings.
+    pub page_size: usize,
+    /// The default tracing filter, used when `RUST_LOG` is unset.
+    pub log_level: Option<String>,
+    /// How long in-flight responses are given to complete on shutdown,
+    /// in seconds.
+    pub shutdown_grace_period_secs: u64,
+    /// The URL the site is publicly reachable at, used for absolute links
+    /// such as those in feeds.
+    pub public_url: String,
+    /// How long the results of read server functions are cached, in
+    /// milliseconds; 0 disables the cache.
+    pub cache_ttl_ms: u64,
+    /// How long a login lasts, in seconds.
+    pub session_ttl_secs: u64,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            storage: StorageConfig::default(),
+            limits: LimitsConfig::default(),
+            security: SecurityConfig::default(),
+            http_cache: HttpCacheConfig::default(),
+            latency_ms: 50,
+            waiter_timeout_ms: 5000,
+            page_size: 20,
+            log_level: None,
+            shutdown_grace_period_secs: 30,
+            public_url: "http://127.0.0.1:4000".to_string(),
+            cache_ttl_ms: 30_000,
+            session_ttl_secs: 86_400,
+        }
+    }
+}
+
+impl AppConfig {
+    /// Load the configuration from, in increasing order of precedence,
+    /// the defaults, the configuration file, the environment and the
+    /// command line arguments.
+    pub fn load(args: &ConfigArgs) -> Result<Self, Box<figment::Error>> {
+        let file = match &args.config {
+            Some(path) => Toml::file_exact(path),
+            None => Toml::file(DEFAULT_CONFIG_FILE),
+        };
+        let config: Self = Figment::from(Serialized::defaults(AppConfig::default()))
+            .merge(file)
+            .merge(Env::prefixed(ENV_PREFIX).split("__").ignore(&["config"]))
+            .merge(args.overrides())
+            .extract()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Reject the values that deserialize but could not be used.
+    fn validate(&self) -> Result<(), Box<figment::Error>> {
+        if let Some(level) = &self.log_level {
+            if let Err(err) = tracing_subscriber::EnvFilter::try_new(level) {
+                let message = format!("invalid log level {level:?}: {err}");
+                return Err(Box::new(
+                    figment::Error::from(message).with_path("log_level"),
+                ));
+            }
+        }
+        let headers = [
+            (
+                "security.content_security_policy",
+                &self.security.content_security_policy,
+            ),
+            ("security.referrer_policy", &self.security.referrer_policy),
+        ];
+        for (path, value) in headers {
+            if !crate::server::security::is_header_value(value) {
+                let message = format!("invalid header value {value:?}");
+                return Err(Box::new(figment::Error::from(message).with_path(path)));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn latency(&self) -> Duration {
+        Duration::from_millis(self.latency_ms)
+    }
+
+    pub fn waiter_timeout(&self) -> Duration {
+        Duration::from_millis(self.waiter_timeout_ms)
+    }
+
+    pub fn shutdown_grace_period(&self) -> Duration {
+        Duration::from_secs(self.shutdown_grace_period_secs)
+    }
+
+    pub fn cache_ttl(&self) -> Duration {
+        Duration::from_millis(self.cache_ttl_ms)
+    }
+
+    pub fn session_ttl(&self) -> Duration {
+        Duration::from_secs(self.session_ttl_secs)
+    }
+}
+
+/// Command line arguments overriding the configuration, accepted by every
+/// command.
+#[derive(clap::Args, Clone, Debug, Default)]
+pub struct ConfigArgs {
+    /// Path to the TOML configuration file
+    #[arg(long, short, global = true, env = "PORTLET_CONFIG")]
+    pub config: Option<PathBuf>,
+    /// Where the content is stored
+    #[arg(long, global = true, value_enum)]
+    pub storage_backend: Option<StorageBackend>,
+    /// Path to the content file used by the json storage backend
+    #[arg(long, global = true)]
+    pub storage_path: Option<PathBuf>,
+    /// Simulated latency added to server functions, in milliseconds
+    #[arg(long, global = true)]
+    pub latency_ms: Option<u64>,
+    /// The longest a portlet waits for its page, in milliseconds
+    #[arg(long, global = true)]
+    pub waiter_timeout_ms: Option<u64>,
+    /// The number of items shown per page in listings
+    #[arg(long, global = true)]
+    pub page_size: Option<usize>,
+    /// The default tracing filter, used when RUST_LOG is unset
+    #[arg(long, global = true)]
+    pub log_level: Option<String>,
+    /// How long in-flight responses are given on shutdown, in seconds
+    #[arg(long, global = true)]
+    pub shutdown_grace_period_secs: Option<u64>,
+    /// The URL the site is publicly reachable at
+    #[arg(long, global = true)]
+    pub public_url: Option<String>,
+    /// How long server function results are cached, in milliseconds
+    #[arg(long, global = true)]
+    pub cache_ttl_ms: Option<u64>,
+    /// How long a login lasts, in seconds
+    #[arg(long, global = true)]
+    pub session_ttl_secs: Option<u64>,
+    /// Calls a minute allowed to the reading server functions
+    #[arg(long, global = true)]
+    pub reads_per_minute: Option<u32>,
+    /// Calls a minute allowed to the writing server functions
+    #[arg(long, global = true)]
+    pub writes_per_minute: Option<u32>,
+    /// The largest request body accepted by a server function, in bytes
+    #[arg(long, global = true)]
+    pub max_body_bytes: Option<usize>,
+    /// The reverse proxies trusted to tell the client IP, separated by commas
+    #[arg(long, global = true, value_delimiter = ',')]
+    pub trusted_proxies: Option<Vec<IpAddr>>,
+    /// The Content-Security-Policy of the pages, {nonce} standing for their nonce
+    #[arg(long, global = true)]
+    pub content_security_policy: Option<String>,
+    /// The max-age of the Strict-Transport-Security header, in seconds
+    #[arg(long, global = true)]
+    pub hsts_max_age_secs: Option<u64>,
+    /// The Referrer-Policy of every response
+    #[arg(long, global = true)]
+    pub referrer_policy: Option<String>,
+    /// How long browsers may cache pages, in seconds; 0 to revalidate them
+    #[arg(long, global = true)]
+    pub html_max_age_secs: Option<u64>,
+    /// How long browsers may cache assets without a hash, in seconds
+    #[arg(long, global = true)]
+    pub asset_max_age_secs: Option<u64>,
+}
+
+impl ConfigArgs {
+    fn overrides(&self) -> Figment {
+        fn set<T: Serialize>(figment: Figment, key: &str, value: &Option<T>) -> Figment {
+            match value {
+                Some(value) => figment.merge(Serialized::default(key, value)),
+                None => figment,
+            }
+        }
+
+        let figment = Figment::new();
+        let figment = set(figment, "storage.backend", &self.storage_backend);
+        let figment = set(figment, "storage.path", &self.storage_path);
+        let figment = set(figment, "latency_ms", &self.latency_ms);
+        let figment = set(figment, "waiter_timeout_ms", &self.waiter_timeout_ms);
+        let figment = set(figment, "page_size", &self.page_size);
+        let figment = set(figment, "log_level", &self.log_level);
+        let figment = set(
+            figment,
+            "shutdown_grace_period_secs",
+            &self.shutdown_grace_period_secs,
+        );
+        let figment = set(figment, "public_url", &self.public_url);
+        let figment = set(figment, "cache_ttl_ms", &self.cache_ttl_ms);
+        let figment = set(figment, "session_ttl_secs", &self.session_ttl_secs);
+        let figment = set(figment, "limits.reads_per_minute", &self.reads_per_minute);
+        let figment = set(figment, "limits.writes_per_minute", &self.writes_per_minute);
+        let figment = set(figment, "limits.max_body_bytes", &self.max_body_bytes);
+        let figment = set(figment, "limits.trusted_proxies", &self.trusted_proxies);
+        let figment = set(
+            figment,
+            "security.content_security_policy",
+            &self.content_security_policy,
+        );
+        let figment = set(
+            figment,
+            "security.hsts_max_age_secs",
+            &self.hsts_max_age_secs,
+        );
+        let figment = set(figment, "security.referrer_policy", &self.referrer_policy);
+        let figment = set(
+            figment,
+            "http_cache.html_max_age_secs",
+            &self.html_max_age_secs,
+        );
+        set(
+            figment,
+            "http_cache.asset_max_age_secs",
+            &self.asset_max_age_secs,
+        )
+    }
+}
@@ -0,0 +1,143 @@
+//! The errors returned by the server functions and shown by the pages of
+//! the app, each mapping onto the HTTP status the page is served with.
+//! Server functions fetching a single author or article fail with
+//! `NotFound` when there is none, rather than returning an `Option`, so
+//! that resources and portlets handle a missing entity like any other
+//! failure.
+
+use leptos::{
+    prelude::*,
+    server_fn::{
+        codec::JsonEncoding,
+        error::{FromServerFnError, ServerFnErrorErr},
+        Bytes, Encodes,
+    },
+};
+use leptos_meta::Title;
+
+#[derive(Clone, Debug, PartialEq, thiserror::Error, serde::Serialize, serde::Deserialize)]
+pub enum AppError {
+    #[error("not found: {0}")]
+    NotFound(String),
+    /// No route of the app matches the path.
+    #[error("no such page")]
+    NoRoute,
+    #[error("bad parameter: {0}")]
+    BadParam(String),
+    #[error("not logged in")]
+    Unauthorized,
+    #[error("forbidden to {0}")]
+    Forbidden(String),
+    #[error("internal error: {0}")]
+    Internal(String),
+}
+
+impl AppError {
+    pub fn status(&self) -> u16 {
+        match self {
+            AppError::NotFound(_) | AppError::NoRoute => 404,
+            AppError::BadParam(_) => 400,
+            AppError::Unauthorized => 401,
+            AppError::Forbidden(_) => 403,
+            AppError::Internal(_) => 500,
+        }
+    }
+
+    /// A description of the error fit for showing to users, leaving out
+    /// the details of internal errors.
+    pub fn message(&self) -> String {
+        match self {
+            AppError::NotFound(what) => format!("There is no {what}."),
+            AppError::NoRoute => "There is no such page.".to_string(),
+            AppError::BadParam(what) => format!("Invalid {what}."),
+            AppError::Unauthorized => "You need to log in first.".to_string(),
+            AppError::Forbidden(what) => format!("You may not {what}."),
+            AppError::Internal(_) => "Something went wrong.".to_string(),
+        }
+    }
+
+    /// The `AppError` behind an error thrown to an `ErrorBoundary`, with
+    /// any other error treated as internal.
+    pub fn from_error(error: &Error) -> Self {
+        error
+            .downcast_ref::<AppError>()
+            .cloned()
+            .unwrap_or_else(|| AppError::Internal(error.to_string()))
+    }
+}
+
+/// Allows `AppError` to be returned by server functions, so that the
+/// variant survives the trip to the client.
+impl FromServerFnError for AppError {
+    type Encoder = JsonEncoding;
+
+    /// Encode the error of a server function called over HTTP, which is
+    /// served with the status matching it; the errors of those called
+    /// while rendering a page are left to the page.
+    fn ser(&self) -> Bytes {
+        set_response_status(self.status());
+        Self::Encoder::encode(self).unwrap_or_else(|error| {
+            Self::Encoder::encode(&Self::from_server_fn_error(
+                ServerFnErrorErr::Serialization(error.to_string()),
+            ))
+            .expect("the serialization error must encode")
+        })
+    }
+
+    fn from_server_fn_error(value: ServerFnErrorErr) -> Self {
+        match value {
+            ServerFnErrorErr::Args(reason) | ServerFnErrorErr::MissingArg(reason) => {
+                AppError::BadParam(reason)
+            }
+            value => AppError::Internal(value.to_string()),
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl From<crate::repository::RepositoryError> for AppError {
+    fn from(error: crate::repository::RepositoryError) -> Self {
+        AppError::Internal(error.to_string())
+    }
+}
+
+/// Set the status of the response being rendered; does nothing outside of
+/// SSR.
+pub fn set_response_status(status: u16) {
+    #[cfg(feature = "axum")]
+    if let Some(response) = use_context::<leptos_axum::ResponseOptions>() {
+        if let Ok(status) = axum::http::StatusCode::from_u16(status) {
+            response.set_status(status);
+        }
+    }
+    #[cfg(feature = "actix")]
+    if let Some(response) = use_context::<leptos_actix::ResponseOptions>() {
+        if let Ok(status) = actix_web::http::StatusCode::from_u16(status) {
+            response.set_status(status);
+        }
+    }
+    #[cfg(not(any(feature = "axum", feature = "actix")))]
+    let _ = status;
+}
+
+/// The page shown in place of the content when it fails to load, served
+/// with the status matching the error.
+#[component]
+pub fn ErrorPage(error: AppError) -> impl IntoView {
+    set_response_status(error.status());
+    let title = match &error {
+        AppError::NotFound(_) | AppError::NoRoute => "Not Found",
+        AppError::BadParam(_) => "Bad Request",
+        AppError::Unauthorized => "Unauthorized",
+        AppError::Forbidden(_) => "Forbidden",
+        AppError::Internal(reason) => {
+            tracing::error!(%reason, "rendering internal error");
+            "Internal Error"
+        }
+    };
+    view! {
+        <Title text=title/>
+        <h2 class="error">{title}</h2>
+        <p>{error.message()}</p>
+    }
+}
@@ -0,0 +1,172 @@
+//! Changes to the content, pushed to open pages as server-sent events so
+//! that they refetch what they show.  Each event is named after the type
+//! of the entity changed and carries its id, such as
+//!
+//! ```text
+//! event: article
+//! data: 3
+//! ```
+
+/// Where the events are served by either server.
+pub const PATH: &str = "/api/events";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Entity {
+    Author,
+    Article,
+}
+
+impl Entity {
+    pub const ALL: &[Entity] = &[Entity::Author, Entity::Article];
+
+    /// The name of the events about the entity type.
+    pub fn name(self) -> &'static str {
+        match self {
+            Entity::Author => "author",
+            Entity::Article => "article",
+        }
+    }
+
+    /// The read server functions whose results depend on entities of the
+    /// type.
+    pub fn reads(self) -> &'static [&'static str] {
+        match self {
+            Entity::Author => &["list_authors", "get_author"],
+            Entity::Article => &["list_articles", "list_articles_by_author", "get_article"],
+        }
+    }
+}
+
+/// An entity that was created or changed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Change {
+    pub entity: Entity,
+    pub id: String,
+}
+
+impl Change {
+    pub fn new(entity: Entity, id: impl ToString) -> Self {
+        Self {
+            entity,
+            id: id.to_string(),
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub use self::ssr::*;
+
+#[cfg(feature = "ssr")]
+mod ssr {
+    use futures::Stream;
+    use std::time::Duration;
+    use tokio::sync::broadcast::{self, error::RecvError};
+
+    use super::{Change, Entity};
+    use crate::server::shutdown;
+
+    pub const CONTENT_TYPE: &str = "text/event-stream";
+
+    /// How many changes a slow subscriber may fall behind by before it
+    /// misses some.
+    const CAPACITY: usize = 64;
+
+    /// How often a comment is sent to keep idle connections open.
+    const KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+    /// The channel the server functions publish changes to and every
+    /// open event stream subscribes to.
+    #[derive(Clone)]
+    pub struct Events {
+        sender: broadcast::Sender<Change>,
+    }
+
+    impl Default for Events {
+        fn default() -> Self {
+            Self {
+                sender: broadcast::channel(CAPACITY).0,
+            }
+        }
+    }
+
+    impl Events {
+        pub fn publish(&self, change: Change) {
+            tracing::debug!(
+                entity = change.entity.name(),
+                id = change.id,
+                "publishing change"
+            );
+            // there being no subscribers is not an error
+            let _ = self.sender.send(change);
+        }
+
+        /// The event stream of a new subscriber, as the frames of the
+        /// response body, ending once shutdown is requested.  A subscriber
+        /// that falls behind is sent a change without an id for every
+        /// entity type, as it cannot tell which were missed.
+        pub fn stream(&self) -> impl Stream<Item = String> + Send + 'static {
+            let receiver = self.sender.subscribe();
+            let keep_alive =
+                tokio::time::interval_at(tokio::time::Instant::now() + KEEP_ALIVE, KEEP_ALIVE);
+            futures::stream::unfold(
+                (receiver, keep_alive),
+                |(mut receiver, mut keep_alive)| async move {
+                    let frame = tokio::select! {
+                        change = receiver.recv() => match change {
+                            Ok(change) => frame(&change),
+                            Err(RecvError::Lagged(missed)) => {
+                                tracing::warn!(missed, "event subscriber fell behind");
+                                Entity::ALL
+                                    .iter()
+                                    .map(|entity| frame(&Change::new(*entity, "")))
+                                    .collect()
+                            }
+                            Err(RecvError::Closed) => return None,
+                        },
+                        _ = keep_alive.tick() => ":\n\n".to_string(),
+                        _ = shutdown::requested() => return None,
+                    };
+                    Some((frame, (receiver, keep_alive)))
+                },
+            )
+        }
+    }
+
+    fn frame(change: &Change) -> String {
+        format!("event: {}\ndata: {}\n\n", change.entity.name(), change.id)
+    }
+}
+
+/// Call `on_change` with every change pushed by the server for as long as
+/// the page is open; does nothing outside of the browser or on statically
+/// exported pages.
+pub fn use_changes(on_change: impl Fn(Change) + 'static) {
+    #[cfg(feature = "hydrate")]
+    if !crate::static_site::is_static() {
+        use std::rc::Rc;
+        use wasm_bindgen::{closure::Closure, JsCast};
+        use web_sys::{EventSource, MessageEvent};
+
+        let source = match EventSource::new(PATH) {
+            Ok(source) => source,
+            Err(error) => {
+                tracing::warn!(?error, "failed to subscribe to changes");
+                return;
+            }
+        };
+        let on_change = Rc::new(on_change);
+        for entity in Entity::ALL.iter().copied() {
+            let on_change = on_change.clone();
+            let listener = Closure::<dyn Fn(MessageEvent)>::new(move |event: MessageEvent| {
+                let id = event.data().as_string().unwrap_or_default();
+                on_change(Change::new(entity, id));
+            });
+            let _ = source
+                .add_event_listener_with_callback(entity.name(), listener.as_ref().unchecked_ref());
+            // the listeners live as long as the page
+            listener.forget();
+        }
+    }
+    #[cfg(not(feature = "hydrate"))]
+    let _ = on_change;
+}
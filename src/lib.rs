@@ -1,11 +1,31 @@
 pub mod app;
+pub mod auth;
+#[cfg(feature = "ssr")]
+pub mod cache;
+#[cfg(feature = "ssr")]
+pub mod cli;
+#[cfg(feature = "ssr")]
+pub mod config;
+pub mod error;
+pub mod events;
+#[cfg(feature = "ssr")]
+pub mod metrics;
 pub mod portlet;
+pub mod query;
+#[cfg(feature = "ssr")]
+pub mod repository;
+#[cfg(feature = "ssr")]
+pub mod server;
+pub mod static_site;
 pub mod sync_await;
+#[cfg(feature = "ssr")]
+pub mod telemetry;
 
 #[cfg(feature = "hydrate")]
 #[wasm_bindgen::prelude::wasm_bindgen]
 pub fn hydrate() {
     use app::*;
     console_error_panic_hook::set_once();
+    static_site::follow_links_natively();
     leptos::mount::hydrate_body(App);
 }
@@ -2,64 +2,98 @@
 #[tokio::main]
 // as an alternative to actix to stop task stealing, but single thread
 // #[tokio::main(flavor = "current_thread")]
-async fn main() {
-    use axum::Router;
+async fn main() -> Result<(), leptos_demo_portlet::server::ServerError> {
+    run().await
+}
+
+#[cfg(feature = "actix")]
+#[actix_web::main]
+async fn main() -> Result<(), leptos_demo_portlet::server::ServerError> {
+    run().await
+}
+
+/// Run the command given on the command line, which both servers share
+/// but for how they `serve`.
+#[cfg(any(feature = "axum", feature = "actix"))]
+async fn run() -> Result<(), leptos_demo_portlet::server::ServerError> {
+    use clap::Parser;
+    use leptos_demo_portlet::{
+        cli::{Cli, Command},
+        config::AppConfig,
+    };
+
+    let cli = Cli::parse();
+    let config = AppConfig::load(&cli.config)?;
+    leptos_demo_portlet::telemetry::init(config.log_level.as_deref());
+    match cli.command.unwrap_or(Command::Serve) {
+        Command::Serve => serve(config).await,
+        Command::Task(task) => Ok(task.run(config).await?),
+    }
+}
+
+#[cfg(feature = "axum")]
+async fn serve(
+    config: leptos_demo_portlet::config::AppConfig,
+) -> Result<(), leptos_demo_portlet::server::ServerError> {
     use leptos::prelude::*;
-    use leptos_axum::{generate_route_list, LeptosRoutes};
 
-    use leptos_demo_portlet::app::{shell, App};
+    use leptos_demo_portlet::{
+        metrics,
+        repository::Repository,
+        server::{self, shutdown, AppState, ServerError},
+    };
 
-    let conf = get_configuration(None).unwrap();
-    let addr = conf.leptos_options.site_addr;
-    let leptos_options = conf.leptos_options;
-    // Generate the list of routes in your Leptos App
-    let routes = generate_route_list(App);
+    metrics::install();
 
-    let app = Router::new()
-        .leptos_routes(&leptos_options, routes, {
-            let leptos_options = leptos_options.clone();
-            move || shell(leptos_options.clone())
-        })
-        .fallback(leptos_axum::file_and_error_handler(shell))
-        .with_state(leptos_options);
+    let conf = get_configuration(None)?;
+    let addr = conf.leptos_options.site_addr;
+    let grace_period = config.shutdown_grace_period();
+    let repository = Repository::open(&config.storage)?;
+    let app = server::axum::router(AppState::new(conf.leptos_options, config, repository));
 
     // run our app with hyper
     // `axum::Server` is a re-export of `hyper::Server`
-    println!("listening on http://{}", &addr);
-    let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
-    axum::serve(listener, app.into_make_service())
+    let listener = tokio::net::TcpListener::bind(&addr)
+        .await
+        .map_err(|source| ServerError::Bind { addr, source })?;
+    tracing::info!("listening on http://{}", &addr);
+    server::axum::serve(listener, app, grace_period, shutdown::signal())
         .await
-        .unwrap();
+        .map_err(|source| ServerError::Serve { addr, source })
 }
 
 #[cfg(feature = "actix")]
-#[actix_web::main]
-async fn main() -> std::io::Result<()> {
-    use actix_files::Files;
+async fn serve(
+    config: leptos_demo_portlet::config::AppConfig,
+) -> Result<(), leptos_demo_portlet::server::ServerError> {
     use actix_web::*;
     use leptos::prelude::*;
-    use leptos_actix::{generate_route_list, LeptosRoutes};
 
-    use leptos_demo_portlet::app::{shell, App};
+    use leptos_demo_portlet::{
+        metrics,
+        repository::Repository,
+        server::{self, shutdown, AppState, ServerError},
+    };
 
-    let conf = get_configuration(None).unwrap();
-    let addr = conf.leptos_options.site_addr;
+    metrics::install();
 
-    HttpServer::new(move || {
-        let routes = generate_route_list(App);
-        let leptos_options = &conf.leptos_options;
-        let site_root = &leptos_options.site_root;
+    let conf = get_configuration(None)?;
+    let addr = conf.leptos_options.site_addr;
+    let grace_period = config.shutdown_grace_period();
+    let repository = Repository::open(&config.storage)?;
+    let state = AppState::new(conf.leptos_options, config, repository);
 
-        App::new()
-            .leptos_routes(routes, {
-                let leptos_options = leptos_options.clone();
-                move || shell(leptos_options.clone())
-            })
-            .service(Files::new("/", site_root.as_ref()))
-    })
-    .bind(&addr)?
-    .run()
-    .await
+    // SIGINT and SIGTERM are handled by `serve` rather than actix, so that
+    // shutdown is requested of the event streams before the workers stop.
+    let server = HttpServer::new(move || server::actix::app(state.clone()))
+        .disable_signals()
+        .shutdown_timeout(grace_period.as_secs())
+        .bind(&addr)
+        .map_err(|source| ServerError::Bind { addr, source })?;
+    tracing::info!("listening on http://{}", &addr);
+    server::actix::serve(server.run(), shutdown::signal())
+        .await
+        .map_err(|source| ServerError::Serve { addr, source })
 }
 
 #[cfg(not(feature = "ssr"))]
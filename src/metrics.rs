@@ -0,0 +1,127 @@
+use leptos::prelude::*;
+use metrics::{counter, gauge, histogram};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use std::{future::Future, sync::OnceLock, time::Instant};
+
+use crate::error::AppError;
+
+static HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+
+const SECONDS_BUCKETS: &[f64] = &[
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// Install the global Prometheus recorder.  Calling this more than once
+/// is harmless; only the first call installs the recorder.
+pub fn install() {
+    HANDLE.get_or_init(|| {
+        PrometheusBuilder::new()
+            .set_buckets_for_metric(Matcher::Suffix("_seconds".to_string()), SECONDS_BUCKETS)
+            .expect("the buckets provided must not be empty")
+            .install_recorder()
+            .expect("a metrics recorder must not already be installed")
+    });
+}
+
+/// Render all recorded metrics in the Prometheus text exposition format.
+pub fn render() -> String {
+    HANDLE
+        .get()
+        .map(PrometheusHandle::render)
+        .unwrap_or_default()
+}
+
+/// Run the body of a server function, recording the call count by
+/// outcome and its latency under the given function name.
+pub async fn server_fn<T>(
+    name: &'static str,
+    fut: impl Future<Output = Result<T, AppError>>,
+) -> Result<T, AppError> {
+    let start = Instant::now();
+    let result = fut.await;
+    let outcome = if result.is_ok() { "ok" } else { "error" };
+    counter!("server_fn_requests_total", "function" => name, "outcome" => outcome).increment(1);
+    histogram!("server_fn_duration_seconds", "function" => name).record(start.elapsed());
+    result
+}
+
+/// Count a lookup in the server function cache by whether it was a hit.
+pub fn record_cache_lookup(function: &'static str, hit: bool) {
+    let result = if hit { "hit" } else { "miss" };
+    counter!("cache_lookups_total", "function" => function, "result" => result).increment(1);
+}
+
+/// Count a call to a server function turned away by the rate limit of
+/// its class.
+pub fn record_rate_limited(class: &'static str) {
+    counter!("rate_limited_total", "class" => class).increment(1);
+}
+
+pub fn record_cache_invalidation(function: &'static str) {
+    counter!("cache_invalidations_total", "function" => function).increment(1);
+}
+
+/// Record the time taken to render the current route, from now until
+/// the reactive owner of the response is cleaned up at the end of the
+/// response stream.  Must be called within the app function.
+pub fn track_ssr_render() {
+    let Some(route) = matched_route() else {
+        return;
+    };
+    let start = Instant::now();
+    on_cleanup(move || {
+        histogram!("ssr_render_duration_seconds", "route" => route).record(start.elapsed());
+    });
+}
+
+#[cfg(feature = "axum")]
+fn matched_route() -> Option<String> {
+    use axum::{extract::MatchedPath, http::request::Parts};
+    use_context::<Parts>()?
+        .extensions
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+}
+
+#[cfg(feature = "actix")]
+fn matched_route() -> Option<String> {
+    use_context::<leptos_actix::Request>()?.match_pattern()
+}
+
+#[cfg(not(any(feature = "axum", feature = "actix")))]
+fn matched_route() -> Option<String> {
+    None
+}
+
+/// Tracks a subscriber parked on a waiter; the gauge is decremented once
+/// this is dropped.
+pub struct WaiterSubscriber(());
+
+impl WaiterSubscriber {
+    pub fn new() -> Self {
+        gauge!("waiter_subscribers").increment(1);
+        Self(())
+    }
+}
+
+impl Default for WaiterSubscriber {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for WaiterSubscriber {
+    fn drop(&mut self) {
+        gauge!("waiter_subscribers").decrement(1);
+    }
+}
+
+pub fn record_waiter_completion(subscribers: usize) {
+    histogram!("waiter_completion_subscribers").record(subscribers as f64);
+}
+
+pub fn record_wait(duration: std::time::Duration) {
+    histogram!("waiter_wait_duration_seconds").record(duration);
+}
@@ -1,12 +1,46 @@
 use leptos::prelude::*;
+use std::{fmt, future::Future, pin::Pin, sync::Arc};
+use tracing::Instrument;
+
+use crate::error::AppError;
 
 #[cfg(feature = "ssr")]
-use crate::sync_await::ssr::Waiter;
+use crate::{config::AppConfig, sync_await::ssr::Waiter};
+
+type Fetch<T> = dyn Fn() -> Pin<Box<dyn Future<Output = Result<T, AppError>> + Send>> + Send + Sync;
+
+/// Where the portlet gets its data from: a resource of its own, or one
+/// of the page mapped into the portlet data, along with how to have that
+/// resource fetch anew.
+#[derive(Clone)]
+struct Source<T> {
+    fetch: Arc<Fetch<T>>,
+    refetch: Arc<dyn Fn() + Send + Sync>,
+}
+
+impl<T> fmt::Debug for Source<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Source")
+    }
+}
+
+/// How to stop the timer refetching a portlet.
+pub type StopTimer = Arc<dyn Fn() + Send + Sync>;
+
+#[derive(Clone)]
+struct Poll(StopTimer);
+
+impl fmt::Debug for Poll {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Poll")
+    }
+}
 
 #[derive(Clone, Debug, Default)]
 pub struct PortletCtx<T> {
-    inner: Option<ArcResource<Result<T, ServerFnError>>>,
+    inner: Option<Source<T>>,
     refresh: RwSignal<usize>,
+    poll: Option<Poll>,
 }
 
 impl<T> PortletCtx<T>
@@ -23,16 +57,114 @@ where
     /// Clear the resource in the portlet.  The component using this
     /// may decide to not render anything.
     pub fn clear(&mut self) {
-        leptos::logging::log!("PortletCtx clear");
+        tracing::debug!(portlet = std::any::type_name::<T>(), "clearing portlet");
+        self.stop_polling();
         self.refresh.try_update(|n| *n += 1);
         self.inner = None;
     }
 
     /// Set the resource for this portlet.
-    pub fn set(&mut self, value: ArcResource<Result<T, ServerFnError>>) {
-        leptos::logging::log!("PortletCtx set");
+    pub fn set(&mut self, value: ArcResource<Result<T, AppError>>) {
+        tracing::debug!(portlet = std::any::type_name::<T>(), "setting portlet");
+        self.stop_polling();
+        self.refresh.try_update(|n| *n += 1);
+        self.inner = Some(Source {
+            fetch: Arc::new({
+                let value = value.clone();
+                move || {
+                    let value = value.clone();
+                    Box::pin(async move { value.await })
+                }
+            }),
+            refetch: Arc::new(move || value.refetch()),
+        });
+    }
+
+    /// Set the resource for this portlet, refetching it every interval
+    /// for as long as it is set and the component setting it is mounted.
+    /// Polling only happens in the browser, so during SSR this is `set`.
+    pub fn set_with_interval(
+        &mut self,
+        value: ArcResource<Result<T, AppError>>,
+        interval: std::time::Duration,
+    ) {
+        #[cfg(feature = "hydrate")]
+        self.set_with_timer(value, |refetch| {
+            let handle = set_interval_with_handle(move || refetch(), interval)
+                .inspect_err(|error| tracing::warn!(?error, "failed to poll portlet"))
+                .ok()?;
+            Some(Arc::new(move || handle.clear()))
+        });
+        #[cfg(not(feature = "hydrate"))]
+        {
+            let _ = interval;
+            self.set(value);
+        }
+    }
+
+    /// Set the resource for this portlet, refetching it on every tick of
+    /// the timer started by `start` with the refetch to call, until the
+    /// portlet is set anew or cleared or the current owner is cleaned up.
+    /// `set_with_interval` starts the interval timer of the browser.
+    pub fn set_with_timer(
+        &mut self,
+        value: ArcResource<Result<T, AppError>>,
+        start: impl FnOnce(Arc<dyn Fn() + Send + Sync>) -> Option<StopTimer>,
+    ) {
+        self.set(value);
+        let Some(Source { refetch, .. }) = &self.inner else {
+            return;
+        };
+        let Some(stop) = start(refetch.clone()) else {
+            return;
+        };
+        on_cleanup({
+            let stop = stop.clone();
+            move || stop()
+        });
+        self.poll = Some(Poll(stop));
+    }
+
+    /// Set the portlet to the data mapped from a resource the page already
+    /// has.  Unlike with `set`, there is no resource of its own to be
+    /// serialized into the hydration payload alongside that of the page.
+    pub fn set_derived<U>(
+        &mut self,
+        resource: Resource<Result<U, AppError>>,
+        map: impl Fn(U) -> T + Send + Sync + 'static,
+    ) where
+        U: serde::Serialize + serde::de::DeserializeOwned + Clone + Send + Sync + 'static,
+    {
+        tracing::debug!(
+            portlet = std::any::type_name::<T>(),
+            "setting portlet derived from resource"
+        );
+        self.stop_polling();
         self.refresh.try_update(|n| *n += 1);
-        self.inner = Some(value);
+        let map = Arc::new(map);
+        self.inner = Some(Source {
+            fetch: Arc::new(move || {
+                let map = map.clone();
+                Box::pin(async move { resource.await.map(|value| map(value)) })
+            }),
+            refetch: Arc::new(move || resource.refetch()),
+        });
+    }
+
+    /// Fetch the data of the portlet anew, such as after a write that
+    /// changes it, keeping what is shown until the new data arrives.
+    /// Resources from `query` bypass its cache when refetched.
+    pub fn refetch(&self) {
+        tracing::debug!(portlet = std::any::type_name::<T>(), "refetching portlet");
+        if let Some(Source { refetch, .. }) = &self.inner {
+            refetch();
+        }
+    }
+
+    fn stop_polling(&mut self) {
+        if let Some(Poll(stop)) = self.poll.take() {
+            stop();
+        }
     }
 
     /// The reason why there is no constructor provided and only done so
@@ -41,12 +173,29 @@ where
         let (rs, ws) = signal(PortletCtx::<T> {
             inner: None,
             refresh: RwSignal::new(0),
+            poll: None,
         });
         provide_context(rs);
         provide_context(ws);
     }
 }
 
+/// The view of a portlet whose resource failed, by kind of failure.
+fn render_error<T>(error: AppError) -> impl IntoView {
+    let class = match &error {
+        AppError::NotFound(_) | AppError::NoRoute => "portlet-error not-found",
+        AppError::BadParam(_) => "portlet-error bad-param",
+        AppError::Unauthorized => "portlet-error unauthorized",
+        AppError::Forbidden(_) => "portlet-error forbidden",
+        AppError::Internal(reason) => {
+            let portlet = std::any::type_name::<T>();
+            tracing::warn!(portlet, %reason, "portlet failed to load");
+            "portlet-error internal"
+        }
+    };
+    view! { <p class=class>{error.message()}</p> }
+}
+
 pub fn render_portlet<T>() -> impl IntoView
 where
     T: serde::Serialize
@@ -62,51 +211,49 @@ where
 {
     #[cfg(feature = "ssr")]
     let waiter = Waiter::maybe();
+    #[cfg(feature = "ssr")]
+    let waiter_timeout = use_context::<AppConfig>()
+        .unwrap_or_default()
+        .waiter_timeout();
 
     let rs = expect_context::<ReadSignal<PortletCtx<T>>>();
     let refresh = rs.get_untracked().refresh;
     let resource = Resource::new_blocking(
-        {
-            move || {
-                leptos::logging::log!("into_render suspend resource signaled!");
-                refresh.get()
-            }
-        },
+        move || refresh.get(),
         move |id| {
-            leptos::logging::log!("refresh id {id}");
             #[cfg(feature = "ssr")]
             let waiter = waiter.clone();
+            let span = tracing::debug_span!(
+                "portlet",
+                portlet = std::any::type_name::<T>(),
+                refresh_id = id,
+            );
             async move {
-                leptos::logging::log!("PortletCtxRender Suspend resource entering");
-                leptos::logging::log!("refresh id {id}");
                 #[cfg(feature = "ssr")]
-                waiter.subscribe().wait().await;
+                waiter.subscribe().wait(waiter_timeout).await;
                 let ctx = rs.get();
-                leptos::logging::log!("portlet_ctx.inner = {:?}", ctx.inner);
-                let result = if let Some(resource) = ctx.inner {
-                    Ok::<_, ServerFnError>(Some(resource.await?))
+                tracing::trace!(inner = ?ctx.inner, "portlet resource awaiting inner");
+                let result = if let Some(Source { fetch, .. }) = ctx.inner {
+                    Ok::<_, AppError>(Some(fetch().await?))
                 } else {
                     Ok(None)
                 };
-                leptos::logging::log!("PortletCtxRender Suspend resource exiting");
+                tracing::debug!(
+                    empty = matches!(result, Ok(None)),
+                    "portlet resource resolved"
+                );
                 result
             }
+            .instrument(span)
         },
     );
 
     let suspend = move || {
         Suspend::new(async move {
-            leptos::logging::log!("PortletCtxRender Suspend entering");
-            let result = resource.await?;
-            let result = if let Some(result) = result {
-                leptos::logging::log!("returning actual view");
-                Ok::<_, ServerFnError>(Some(result.into_render().into_any()))
-            } else {
-                leptos::logging::log!("returning empty view");
-                Ok(None)
-            };
-            leptos::logging::log!("PortletCtxRender Suspend exiting");
-            result
+            match resource.await {
+                Ok(result) => result.map(|result| result.into_render().into_any()),
+                Err(error) => Some(render_error::<T>(error).into_any()),
+            }
         })
     };
 
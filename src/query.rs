@@ -0,0 +1,338 @@
+//! A client side cache of server function results, keyed by the function
+//! and its serialized arguments, so that navigating back to a page does
+//! not refetch its resources.  Cached results are served immediately; once
+//! they are older than [`FRESH_FOR`] they are also revalidated in the
+//! background, rerunning the resources using them should the result have
+//! changed.  Results older than [`MAX_AGE`] are swept at most once a
+//! minute.  Refetching a resource from [`resource`] or [`arc_resource`]
+//! bypasses the cache.
+//!
+//! On the server every request starts out empty, so results are always
+//! fetched there.
+
+use leptos::prelude::*;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    future::Future,
+    sync::{Arc, Mutex},
+};
+
+use crate::error::AppError;
+
+/// How long a cached result is served without being revalidated, in
+/// milliseconds.
+pub const FRESH_FOR: f64 = 10_000.0;
+
+/// How long a cached result is served at all, in milliseconds.
+pub const MAX_AGE: f64 = 300_000.0;
+
+/// How often results older than [`MAX_AGE`] are swept, in milliseconds.
+#[cfg(feature = "hydrate")]
+const SWEEP_EVERY: f64 = 60_000.0;
+
+#[cfg(feature = "hydrate")]
+mod client {
+    use leptos::prelude::*;
+    use std::{
+        any::Any,
+        cell::{Cell, RefCell},
+        collections::HashMap,
+        future::Future,
+    };
+
+    use super::{FRESH_FOR, MAX_AGE, SWEEP_EVERY};
+    use crate::error::AppError;
+
+    /// The function and its arguments serialized as JSON.
+    pub type Key = (&'static str, String);
+
+    struct Entry {
+        value: Box<dyn Any>,
+        fetched: f64,
+    }
+
+    thread_local! {
+        static ENTRIES: RefCell<HashMap<Key, Entry>> = RefCell::new(HashMap::new());
+        static VERSIONS: RefCell<HashMap<Key, ArcRwSignal<usize>>> = RefCell::new(HashMap::new());
+        static SWEPT: Cell<f64> = const { Cell::new(0.0) };
+    }
+
+    pub fn now() -> f64 {
+        js_sys::Date::now()
+    }
+
+    /// The cached value with its age, if there is one of the type.
+    pub fn get<T: Clone + 'static>(key: &Key) -> Option<(T, f64)> {
+        ENTRIES.with_borrow(|entries| {
+            let entry = entries.get(key)?;
+            let value = entry.value.downcast_ref::<T>()?.clone();
+            Some((value, now() - entry.fetched))
+        })
+    }
+
+    /// Cache the value, returning whether it differs from the one it
+    /// replaces.
+    pub fn insert<T: PartialEq + 'static>(key: Key, value: T) -> bool {
+        sweep();
+        ENTRIES.with_borrow_mut(|entries| {
+            let changed = entries
+                .get(&key)
+                .and_then(|entry| entry.value.downcast_ref::<T>())
+                != Some(&value);
+            entries.insert(
+                key,
+                Entry {
+                    value: Box::new(value),
+                    fetched: now(),
+                },
+            );
+            changed
+        })
+    }
+
+    /// Cache the value unless there already is one, such as for values
+    /// taken from the SSR output while hydrating.
+    pub fn seed<T: 'static>(key: Key, value: T) {
+        sweep();
+        ENTRIES.with_borrow_mut(|entries| {
+            entries.entry(key).or_insert_with(|| Entry {
+                value: Box::new(value),
+                fetched: now(),
+            });
+        });
+    }
+
+    pub async fn cached<T, Fut>(key: Key, fetch: impl Fn() -> Fut + 'static) -> Result<T, AppError>
+    where
+        T: Clone + PartialEq + 'static,
+        Fut: Future<Output = Result<T, AppError>> + 'static,
+    {
+        match get::<T>(&key) {
+            Some((value, age)) if age < FRESH_FOR => {
+                tracing::trace!(?key, "query cache hit");
+                Ok(value)
+            }
+            Some((value, age)) if age < MAX_AGE => {
+                tracing::trace!(?key, "query cache hit, revalidating");
+                leptos::task::spawn_local(async move {
+                    if let Ok(value) = fetch().await {
+                        if insert(key.clone(), value) {
+                            version(&key).update(|n| *n += 1);
+                        }
+                    }
+                });
+                Ok(value)
+            }
+            _ => {
+                tracing::trace!(?key, "query cache miss");
+                let value = fetch().await?;
+                insert(key, value.clone());
+                Ok(value)
+            }
+        }
+    }
+
+    /// Fetch the value anew rather than serving the cached one, rerunning
+    /// the resources using it should it have changed.
+    pub async fn refetch<T, Fut>(key: Key, fetch: impl Fn() -> Fut) -> Result<T, AppError>
+    where
+        T: Clone + PartialEq + 'static,
+        Fut: Future<Output = Result<T, AppError>>,
+    {
+        tracing::trace!(?key, "query cache bypassed");
+        let value = fetch().await?;
+        if insert(key.clone(), value.clone()) {
+            version(&key).update(|n| *n += 1);
+        }
+        Ok(value)
+    }
+
+    pub fn version(key: &Key) -> ArcRwSignal<usize> {
+        VERSIONS.with_borrow_mut(|versions| versions.entry(key.clone()).or_default().clone())
+    }
+
+    /// Drop the cached results of the functions, rerunning the resources
+    /// using them.
+    pub fn invalidate(functions: &[&'static str]) {
+        ENTRIES.with_borrow_mut(|entries| {
+            entries.retain(|(function, _), _| !functions.contains(function))
+        });
+        drop_versions(|(function, _)| functions.contains(function));
+    }
+
+    /// Drop the results older than `MAX_AGE`, unless swept within the
+    /// last `SWEEP_EVERY`.
+    fn sweep() {
+        let now = now();
+        if now - SWEPT.get() < SWEEP_EVERY {
+            return;
+        }
+        SWEPT.set(now);
+        let mut expired = Vec::new();
+        ENTRIES.with_borrow_mut(|entries| {
+            entries.retain(|key, entry| {
+                let keep = now - entry.fetched < MAX_AGE;
+                if !keep {
+                    expired.push(key.clone());
+                }
+                keep
+            })
+        });
+        drop_versions(|key| expired.contains(key));
+    }
+
+    /// Drop the versions of the keys, rerunning the resources tracking
+    /// them, which look up new ones as they do.
+    fn drop_versions(dropped: impl Fn(&Key) -> bool) {
+        let versions = VERSIONS.with_borrow_mut(|versions| {
+            let (dropped, kept): (HashMap<_, _>, _) =
+                versions.drain().partition(|(key, _)| dropped(key));
+            *versions = kept;
+            dropped
+        });
+        // updated outside of the borrow, as the resources rerun by this
+        // look up their versions
+        for (_, version) in versions {
+            version.update(|n| *n += 1);
+        }
+    }
+}
+
+/// The key of the function and arguments, if the arguments serialize;
+/// results for arguments that do not are not cached.
+#[cfg(feature = "hydrate")]
+fn key(function: &'static str, args: &impl Serialize) -> Option<client::Key> {
+    Some((function, serde_json::to_string(args).ok()?))
+}
+
+/// Drop the cached results of the functions for all arguments, such as
+/// once they are known to have changed, rerunning the resources using
+/// them; does nothing outside of the browser.
+pub fn invalidate(functions: &[&'static str]) {
+    #[cfg(feature = "hydrate")]
+    client::invalidate(functions);
+    #[cfg(not(feature = "hydrate"))]
+    let _ = functions;
+}
+
+/// The number of times the cached result of the function for the
+/// arguments has changed, tracked so that a resource including it in its
+/// source reruns on the revalidated result even though its arguments are
+/// unchanged.
+pub fn version(function: &'static str, args: &impl Serialize) -> usize {
+    #[cfg(feature = "hydrate")]
+    return key(function, args).map_or(0, |key| client::version(&key).get());
+    #[cfg(not(feature = "hydrate"))]
+    {
+        let _ = (function, args);
+        0
+    }
+}
+
+/// The cached result of the function for the arguments, or else the
+/// result of `fetch`, which is cached when successful.
+pub async fn cached<T, Fut>(
+    function: &'static str,
+    args: impl Serialize,
+    fetch: impl Fn() -> Fut + Send + Sync + 'static,
+) -> Result<T, AppError>
+where
+    T: Clone + PartialEq + Send + Sync + 'static,
+    Fut: Future<Output = Result<T, AppError>> + Send + 'static,
+{
+    fetch_through(function, args, false, fetch).await
+}
+
+/// As [`cached`], but fetching anew when refetching.
+async fn fetch_through<T, Fut>(
+    function: &'static str,
+    args: impl Serialize,
+    refetch: bool,
+    fetch: impl Fn() -> Fut + Send + Sync + 'static,
+) -> Result<T, AppError>
+where
+    T: Clone + PartialEq + Send + Sync + 'static,
+    Fut: Future<Output = Result<T, AppError>> + Send + 'static,
+{
+    #[cfg(feature = "hydrate")]
+    if let Some(key) = key(function, &args) {
+        return match refetch {
+            true => client::refetch(key, fetch).await,
+            false => client::cached(key, fetch).await,
+        };
+    }
+    let _ = (function, args, refetch);
+    fetch().await
+}
+
+/// A blocking resource fetching through the cache, for use in place of
+/// `Resource::new_blocking` with a server function as its fetcher.
+pub fn resource<S, T, Fut>(
+    function: &'static str,
+    source: impl Fn() -> S + Send + Sync + 'static,
+    fetch: impl Fn(S) -> Fut + Clone + Send + Sync + 'static,
+) -> Resource<Result<T, AppError>>
+where
+    S: Serialize + Clone + PartialEq + Send + Sync + 'static,
+    T: Serialize + DeserializeOwned + Clone + PartialEq + Send + Sync + 'static,
+    Fut: Future<Output = Result<T, AppError>> + Send + 'static,
+{
+    arc_resource(function, source, fetch).into()
+}
+
+/// As [`resource`], but as an `ArcResource`, such as those given to
+/// `PortletCtx::set`.
+pub fn arc_resource<S, T, Fut>(
+    function: &'static str,
+    source: impl Fn() -> S + Send + Sync + 'static,
+    fetch: impl Fn(S) -> Fut + Clone + Send + Sync + 'static,
+) -> ArcResource<Result<T, AppError>>
+where
+    S: Serialize + Clone + PartialEq + Send + Sync + 'static,
+    T: Serialize + DeserializeOwned + Clone + PartialEq + Send + Sync + 'static,
+    Fut: Future<Output = Result<T, AppError>> + Send + 'static,
+{
+    let source = Arc::new(source);
+    // the arguments and version last fetched, as a fetch for the same ones
+    // is a refetch of the resource, which bypasses the cache
+    let last = Arc::new(Mutex::new(None::<(S, usize)>));
+    let resource = ArcResource::new_blocking(
+        {
+            let source = source.clone();
+            move || {
+                let args = source();
+                let version = version(function, &args);
+                (args, version)
+            }
+        },
+        {
+            let last = last.clone();
+            move |(args, version)| {
+                let fetch = fetch.clone();
+                let current = (args.clone(), version);
+                let refetch = last
+                    .lock()
+                    .is_ok_and(|mut last| last.replace(current.clone()) == Some(current));
+                fetch_through(function, args.clone(), refetch, move || fetch(args.clone()))
+            }
+        },
+    );
+    // results hydrated from the SSR output never pass through `cached`
+    #[cfg(feature = "hydrate")]
+    Effect::new({
+        let resource = resource.clone();
+        move |_| {
+            if let Some(Ok(value)) = resource.get() {
+                let args = untrack(|| source());
+                let version = untrack(|| version(function, &args));
+                if let Ok(mut last) = last.lock() {
+                    *last = Some((args.clone(), version));
+                }
+                if let Some(key) = key(function, &args) {
+                    client::seed(key, value);
+                }
+            }
+        }
+    });
+    resource
+}
@@ -0,0 +1,461 @@
+use crate::{
+    app::{Article, Author},
+    auth::Role,
+    config::{StorageBackend, StorageConfig},
+};
+use serde_json::{Map, Value};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+
+/// The version of the document format written by the json backend.
+pub const SCHEMA_VERSION: u64 = 3;
+
+/// Migrations upgrading a stored document by a single version, starting
+/// from the version matching the index of the migration.
+const MIGRATIONS: [fn(&mut Map<String, Value>); SCHEMA_VERSION as usize] = [
+    // 0 -> 1: unversioned documents only lack the version itself.
+    |_| {},
+    // 1 -> 2: users were added, with none to begin with.
+    |document| {
+        document
+            .entry("users")
+            .or_insert_with(|| Value::Object(Map::new()));
+    },
+    // 2 -> 3: users were given roles, as authors when named after one.
+    |document| {
+        let authors = document.get("authors").and_then(Value::as_object).cloned();
+        let users = document.get_mut("users").and_then(Value::as_object_mut);
+        for (username, user) in users.into_iter().flatten() {
+            let role = match &authors {
+                Some(authors) if authors.contains_key(username) => "author",
+                _ => "reader",
+            };
+            if let Some(user) = user.as_object_mut() {
+                user.entry("role").or_insert_with(|| role.into());
+            }
+        }
+    },
+];
+
+/// The hash of the password `demo`, computed ahead of time as hashing is
+/// deliberately slow.
+const DEMO_PASSWORD_HASH: &str =
+    "$argon2id$v=19$m=19456,t=2,p=1$AFfeKyTwOFB6n21BNed+sA$7u08B4UaRJcd5XilSGNimbPCe4CLt+x9CFagtZAxvMQ";
+
+#[derive(Debug, thiserror::Error)]
+pub enum RepositoryError {
+    #[error("repository lock poisoned")]
+    Poisoned,
+    #[error("failed to access {}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("invalid content in {}: {source}", path.display())]
+    Json {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    #[error(
+        "{} is at schema version {version}; run the `migrate` command to upgrade it",
+        path.display()
+    )]
+    Outdated { path: PathBuf, version: u64 },
+    #[error(
+        "{} is at schema version {version}, newer than the supported version {SCHEMA_VERSION}",
+        path.display()
+    )]
+    UnsupportedVersion { path: PathBuf, version: u64 },
+    #[error("{} already exists; use --force to replace it", path.display())]
+    Exists { path: PathBuf },
+    #[error("the memory backend cannot be written to; configure the json backend instead")]
+    NotPersistent,
+}
+
+/// An account able to log in, keyed by its username.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct User {
+    /// The argon2 hash of the password in PHC string format.
+    pub password_hash: String,
+    pub role: Role,
+}
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct Content {
+    pub authors: BTreeMap<String, Author>,
+    pub articles: BTreeMap<u32, Article>,
+    pub users: BTreeMap<String, User>,
+}
+
+#[derive(Clone, Debug, Default)]
+enum Backend {
+    #[default]
+    Memory,
+    Json(PathBuf),
+}
+
+/// The backend holding all authors and articles, shared between the
+/// server functions and any other endpoints that need the content.
+#[derive(Clone, Debug, Default)]
+pub struct Repository {
+    content: Arc<RwLock<Content>>,
+    backend: Backend,
+}
+
+impl From<(&'static str, &'static str)> for Author {
+    fn from((name, email): (&'static str, &'static str)) -> Self {
+        Author {
+            name: name.to_string(),
+            email: email.to_string(),
+        }
+    }
+}
+
+impl From<(&'static str, &'static str)> for Article {
+    fn from((author_name, title): (&'static str, &'static str)) -> Self {
+        Article {
+            author_name: author_name.to_string(),
+            title: title.to_string(),
+        }
+    }
+}
+
+impl Content {
+    /// The authors, articles and users used by the demo, with a user for
+    /// every author, of which one is an admin and one an editor, and a
+    /// reader, all with the password `demo`.
+    pub fn demo() -> Self {
+        let authors = [
+            ("albert", ("Albert", "albert.g@example.com")),
+            ("bethany", ("Bethany", "beth@example.com")),
+            ("carl", ("Carl", "c.smith@example.com")),
+            ("dorothy", ("Dorothy", "dorothy@example.com")),
+        ]
+        .into_iter()
+        .map(|(id, author)| (id.to_string(), author.into()))
+        .collect();
+        let articles = (1..)
+            .zip([
+                ("dorothy", "The top twenty..."),
+                ("albert", "On the practical nature of..."),
+                ("bethany", "How to guide to..."),
+                ("dorothy", "The top ten..."),
+                ("albert", "Why a city's infrastructure..."),
+                ("bethany", "The ultimate guide to..."),
+                ("dorothy", "The top hundred..."),
+                ("carl", "A quick summary on..."),
+                ("dorothy", "The top thousand..."),
+                ("bethany", "Beware of..."),
+            ])
+            .map(|(id, article)| (id, article.into()))
+            .collect();
+        let users = [
+            ("albert", Role::Admin),
+            ("bethany", Role::Editor),
+            ("carl", Role::Author),
+            ("dorothy", Role::Author),
+            ("erin", Role::Reader),
+        ]
+        .into_iter()
+        .map(|(name, role)| {
+            let user = User {
+                password_hash: DEMO_PASSWORD_HASH.to_string(),
+                role,
+            };
+            (name.to_string(), user)
+        })
+        .collect();
+        Self {
+            authors,
+            articles,
+            users,
+        }
+    }
+
+    /// Load a stored document, which must be at the current version.
+    pub fn load(path: &Path) -> Result<Self, RepositoryError> {
+        let mut document = read_document(path)?;
+        match document_version(&document) {
+            SCHEMA_VERSION => (),
+            version if version < SCHEMA_VERSION => {
+                return Err(RepositoryError::Outdated {
+                    path: path.to_path_buf(),
+                    version,
+                })
+            }
+            version => {
+                return Err(RepositoryError::UnsupportedVersion {
+                    path: path.to_path_buf(),
+                    version,
+                })
+            }
+        }
+        document.remove("version");
+        from_document(path, document)
+    }
+
+    /// Load a document from any prior version, such as a fixture, without
+    /// modifying the file itself.
+    pub fn load_migrated(path: &Path) -> Result<Self, RepositoryError> {
+        let mut document = read_document(path)?;
+        upgrade(path, &mut document)?;
+        document.remove("version");
+        from_document(path, document)
+    }
+
+    /// Write out the content as a document at the current version.
+    pub fn save(&self, path: &Path) -> Result<(), RepositoryError> {
+        let io_error = |source| RepositoryError::Io {
+            path: path.to_path_buf(),
+            source,
+        };
+        // write to a sibling before renaming so readers never see a
+        // partially written document
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, self.to_json()).map_err(io_error)?;
+        std::fs::rename(&tmp, path).map_err(io_error)
+    }
+
+    /// The content as a pretty printed document at the current version.
+    pub fn to_json(&self) -> String {
+        let mut document = match serde_json::to_value(self) {
+            Ok(Value::Object(document)) => document,
+            _ => unreachable!("content always serializes to an object"),
+        };
+        document.insert("version".to_string(), SCHEMA_VERSION.into());
+        serde_json::to_string_pretty(&document).expect("content must serialize")
+    }
+}
+
+fn read_document(path: &Path) -> Result<Map<String, Value>, RepositoryError> {
+    let file = std::fs::File::open(path).map_err(|source| RepositoryError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    serde_json::from_reader(std::io::BufReader::new(file)).map_err(|source| RepositoryError::Json {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+fn from_document(path: &Path, document: Map<String, Value>) -> Result<Content, RepositoryError> {
+    serde_json::from_value(Value::Object(document)).map_err(|source| RepositoryError::Json {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+fn document_version(document: &Map<String, Value>) -> u64 {
+    document.get("version").and_then(Value::as_u64).unwrap_or(0)
+}
+
+/// Apply all outstanding migrations to the document, returning the
+/// version it was originally at.
+fn upgrade(path: &Path, document: &mut Map<String, Value>) -> Result<u64, RepositoryError> {
+    let version = document_version(document);
+    if version > SCHEMA_VERSION {
+        return Err(RepositoryError::UnsupportedVersion {
+            path: path.to_path_buf(),
+            version,
+        });
+    }
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(document);
+    }
+    document.insert("version".to_string(), SCHEMA_VERSION.into());
+    Ok(version)
+}
+
+/// Upgrade the document stored at the path to the current version in
+/// place, returning the version it was originally at.
+pub fn migrate(path: &Path) -> Result<u64, RepositoryError> {
+    let mut document = read_document(path)?;
+    let version = upgrade(path, &mut document)?;
+    if version < SCHEMA_VERSION {
+        document.remove("version");
+        from_document(path, document)?.save(path)?;
+    }
+    Ok(version)
+}
+
+/// Write the content as the initial document at the path, which must not
+/// already exist unless `force` is set.
+pub fn seed(path: &Path, content: &Content, force: bool) -> Result<(), RepositoryError> {
+    if !force && path.exists() {
+        return Err(RepositoryError::Exists {
+            path: path.to_path_buf(),
+        });
+    }
+    content.save(path)
+}
+
+impl Repository {
+    pub fn new(content: Content) -> Self {
+        Self {
+            content: Arc::new(RwLock::new(content)),
+            backend: Backend::Memory,
+        }
+    }
+
+    pub fn demo() -> Self {
+        Self::new(Content::demo())
+    }
+
+    /// Open the repository using the configured storage backend.
+    pub fn open(config: &StorageConfig) -> Result<Self, RepositoryError> {
+        match config.backend {
+            StorageBackend::Memory => Ok(Self::demo()),
+            StorageBackend::Json => Ok(Self {
+                content: Arc::new(RwLock::new(Content::load(&config.path)?)),
+                backend: Backend::Json(config.path.clone()),
+            }),
+        }
+    }
+
+    fn read(&self) -> Result<RwLockReadGuard<'_, Content>, RepositoryError> {
+        self.content.read().map_err(|_| RepositoryError::Poisoned)
+    }
+
+    fn write(&self) -> Result<RwLockWriteGuard<'_, Content>, RepositoryError> {
+        self.content.write().map_err(|_| RepositoryError::Poisoned)
+    }
+
+    /// Apply the change to a copy of the content, which replaces the
+    /// content once it has been persisted by the backend.
+    fn modify<T>(&self, change: impl FnOnce(&mut Content) -> T) -> Result<T, RepositoryError> {
+        let mut content = self.write()?;
+        let mut modified = content.clone();
+        let result = change(&mut modified);
+        if let Backend::Json(path) = &self.backend {
+            modified.save(path)?;
+        }
+        *content = modified;
+        Ok(result)
+    }
+
+    /// A copy of all the content currently held.
+    pub fn snapshot(&self) -> Result<Content, RepositoryError> {
+        Ok(self.read()?.clone())
+    }
+
+    /// Verify that the repository is able to serve content.
+    pub fn check(&self) -> Result<(), RepositoryError> {
+        drop(self.read()?);
+        match &self.backend {
+            Backend::Memory => Ok(()),
+            Backend::Json(path) => {
+                std::fs::metadata(path)
+                    .map(|_| ())
+                    .map_err(|source| RepositoryError::Io {
+                        path: path.clone(),
+                        source,
+                    })
+            }
+        }
+    }
+
+    pub fn list_authors(&self) -> Result<Vec<(String, Author)>, RepositoryError> {
+        Ok(self
+            .read()?
+            .authors
+            .iter()
+            .map(|(id, author)| (id.clone(), author.clone()))
+            .collect())
+    }
+
+    pub fn get_author(&self, name: &str) -> Result<Option<(String, Author)>, RepositoryError> {
+        Ok(self
+            .read()?
+            .authors
+            .get_key_value(name)
+            .map(|(id, author)| (id.clone(), author.clone())))
+    }
+
+    pub fn list_articles(&self) -> Result<Vec<(u32, Article)>, RepositoryError> {
+        Ok(self
+            .read()?
+            .articles
+            .iter()
+            .map(|(id, article)| (*id, article.clone()))
+            .collect())
+    }
+
+    pub fn list_articles_by_author(
+        &self,
+        name: &str,
+    ) -> Result<Vec<(u32, Article)>, RepositoryError> {
+        Ok(self
+            .read()?
+            .articles
+            .iter()
+            .filter(|(_, article)| article.author_name == name)
+            .map(|(id, article)| (*id, article.clone()))
+            .collect())
+    }
+
+    pub fn get_article(&self, id: u32) -> Result<Option<Article>, RepositoryError> {
+        Ok(self.read()?.articles.get(&id).cloned())
+    }
+
+    /// Add the article, returning its newly assigned id.
+    pub fn create_article(&self, article: Article) -> Result<u32, RepositoryError> {
+        self.modify(|content| {
+            let id = content.articles.keys().next_back().map_or(1, |id| id + 1);
+            content.articles.insert(id, article);
+            id
+        })
+    }
+
+    pub fn get_user(&self, username: &str) -> Result<Option<User>, RepositoryError> {
+        Ok(self.read()?.users.get(username).cloned())
+    }
+
+    /// Set the password hash of the user, adding the user as a reader if
+    /// there is none with the username.
+    pub fn set_password(
+        &self,
+        username: &str,
+        password_hash: String,
+    ) -> Result<(), RepositoryError> {
+        self.modify(|content| {
+            content
+                .users
+                .entry(username.to_string())
+                .and_modify(|user| user.password_hash.clone_from(&password_hash))
+                .or_insert_with(|| User {
+                    password_hash,
+                    role: Role::Reader,
+                });
+        })
+    }
+
+    /// Set the role of the user, returning whether there is one with the
+    /// username.
+    pub fn set_role(&self, username: &str, role: Role) -> Result<bool, RepositoryError> {
+        self.modify(|content| {
+            content
+                .users
+                .get_mut(username)
+                .map(|user| user.role = role)
+                .is_some()
+        })
+    }
+
+    /// Change the title of the article, returning the updated article if
+    /// there is one with the id.
+    pub fn update_article(
+        &self,
+        id: u32,
+        title: String,
+    ) -> Result<Option<Article>, RepositoryError> {
+        self.modify(|content| {
+            content.articles.get_mut(&id).map(|article| {
+                article.title = title;
+                article.clone()
+            })
+        })
+    }
+}
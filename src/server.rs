@@ -0,0 +1,269 @@
+//! The parts of the HTTP servers that live outside of the Leptos app,
+//! with the framework specific wiring kept in the `axum` and `actix`
+//! submodules so that both servers behave identically.
+
+use leptos::{config::errors::LeptosConfigError, prelude::*, server_fn::ServerFn};
+use leptos_router::{Method, SsrMode};
+use std::{fmt, net::SocketAddr};
+
+use self::limits::RateLimiter;
+use crate::{
+    app::{shell, CreateArticle, UpdateArticle},
+    auth::{Login, Logout, Sessions},
+    cache::Cache,
+    cli::CliError,
+    config::AppConfig,
+    events::Events,
+    metrics,
+    repository::{Repository, RepositoryError},
+};
+
+#[cfg(feature = "actix")]
+pub mod actix;
+pub mod api;
+pub mod assets;
+#[cfg(feature = "axum")]
+pub mod axum;
+pub mod csrf;
+pub mod feed;
+pub mod limits;
+pub mod security;
+pub mod shutdown;
+pub mod sitemap;
+pub mod static_site;
+
+/// The server functions that write, being those that change content or
+/// guard the logins, limited to the rate of writes, each with whether its
+/// requests must carry the CSRF token of their session: all but `login`,
+/// which starts the session.
+const WRITES: &[(&str, bool)] = &[
+    (CreateArticle::PATH, true),
+    (UpdateArticle::PATH, true),
+    (Login::PATH, false),
+    (Logout::PATH, true),
+];
+
+/// Failures that prevent the server from starting or running, or the
+/// command run instead of it from completing.
+#[derive(thiserror::Error)]
+pub enum ServerError {
+    #[error("failed to load configuration: {0}")]
+    Config(#[from] LeptosConfigError),
+    #[error("invalid application configuration: {0}")]
+    AppConfig(#[from] Box<figment::Error>),
+    #[error("repository error: {0}")]
+    Repository(#[from] RepositoryError),
+    #[error("failed to bind to http://{addr}: {source}")]
+    Bind {
+        addr: SocketAddr,
+        source: std::io::Error,
+    },
+    #[error("server on http://{addr} failed: {source}")]
+    Serve {
+        addr: SocketAddr,
+        source: std::io::Error,
+    },
+    #[error(transparent)]
+    Task(#[from] CliError),
+}
+
+// `main` reports a returned error using `Debug`, so make that readable.
+impl fmt::Debug for ServerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// Everything the handlers of either server need, cheap to clone.
+#[derive(Clone)]
+pub struct AppState {
+    pub leptos_options: LeptosOptions,
+    pub config: AppConfig,
+    pub repository: Repository,
+    pub cache: Cache,
+    pub events: Events,
+    pub sessions: Sessions,
+    pub limiter: RateLimiter,
+}
+
+impl AppState {
+    /// The state with an empty cache, no sessions and no calls counted
+    /// against the rate limits, configured by `config`, and no event
+    /// subscribers.
+    pub fn new(leptos_options: LeptosOptions, config: AppConfig, repository: Repository) -> Self {
+        Self {
+            leptos_options,
+            cache: Cache::new(config.cache_ttl()),
+            events: Events::default(),
+            sessions: Sessions::new(config.session_ttl()),
+            limiter: RateLimiter::new(config.limits.clone()),
+            config,
+            repository,
+        }
+    }
+
+    /// Provide the parts of the state used by the app and server
+    /// functions as context.
+    pub fn provide_contexts(&self) {
+        provide_context(self.config.clone());
+        provide_context(self.repository.clone());
+        provide_context(self.cache.clone());
+        provide_context(self.events.clone());
+        provide_context(self.sessions.clone());
+    }
+}
+
+/// The app function passed to the Leptos integrations, rendering the
+/// shell while tracking the render for metrics and shutdown.
+fn render(options: LeptosOptions) -> impl IntoView {
+    metrics::track_ssr_render();
+    shutdown::track_render();
+    shell(options)
+}
+
+/// A route rendered by the app, as registered with the server.
+pub struct RouteInfo {
+    pub path: String,
+    pub mode: SsrMode,
+    pub methods: Vec<Method>,
+}
+
+impl fmt::Display for RouteInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mode = match self.mode {
+            SsrMode::OutOfOrder => "out-of-order",
+            SsrMode::PartiallyBlocked => "partially-blocked",
+            SsrMode::InOrder => "in-order",
+            SsrMode::Async => "async",
+            SsrMode::Static(_) => "static",
+        };
+        let methods = self
+            .methods
+            .iter()
+            .map(|method| format!("{method:?}").to_uppercase())
+            .collect::<Vec<_>>()
+            .join(",");
+        write!(f, "{:<40} {mode:<18} {methods}", self.path)
+    }
+}
+
+/// The routes of the app as generated by the integration in use.
+pub fn route_list() -> Vec<RouteInfo> {
+    #[cfg(feature = "axum")]
+    {
+        axum::route_list()
+    }
+    #[cfg(all(feature = "actix", not(feature = "axum")))]
+    {
+        actix::route_list()
+    }
+    #[cfg(not(any(feature = "axum", feature = "actix")))]
+    {
+        Vec::new()
+    }
+}
+
+/// A response built outside of the Leptos app, such as a feed.
+pub struct Document {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+impl Document {
+    const TEXT: &'static str = "text/plain; charset=utf-8";
+
+    /// The document built from the repository, or a 404 when there is no
+    /// such document.
+    fn found(content_type: &'static str, result: Result<Option<String>, RepositoryError>) -> Self {
+        match result {
+            Ok(Some(body)) => Self {
+                status: 200,
+                content_type,
+                body,
+            },
+            Ok(None) => Self {
+                status: 404,
+                content_type: Self::TEXT,
+                body: "not found".to_string(),
+            },
+            Err(err) => {
+                tracing::error!(%err, "failed to build {content_type} document");
+                Self {
+                    status: 500,
+                    content_type: Self::TEXT,
+                    body: "internal server error".to_string(),
+                }
+            }
+        }
+    }
+}
+
+/// The feed of all articles.
+pub fn feed(state: &AppState) -> Document {
+    Document::found(
+        feed::CONTENT_TYPE,
+        feed::articles(&state.repository, &state.config).map(Some),
+    )
+}
+
+/// The feed of the articles by a single author.
+pub fn author_feed(state: &AppState, name: &str) -> Document {
+    Document::found(
+        feed::CONTENT_TYPE,
+        feed::articles_by_author(&state.repository, &state.config, name),
+    )
+}
+
+/// The sitemap of every page in the repository.
+pub fn sitemap(state: &AppState) -> Document {
+    Document::found(
+        sitemap::CONTENT_TYPE,
+        sitemap::sitemap(&state.repository, &state.config).map(Some),
+    )
+}
+
+pub fn robots(state: &AppState) -> Document {
+    Document::found(
+        sitemap::ROBOTS_CONTENT_TYPE,
+        Ok(Some(sitemap::robots(&state.config))),
+    )
+}
+
+/// The outcome of a health check, as a status code and a plain text body.
+pub struct Health {
+    pub status: u16,
+    pub body: String,
+}
+
+impl Health {
+    fn ok() -> Self {
+        Self {
+            status: 200,
+            body: "ok".to_string(),
+        }
+    }
+
+    fn unavailable(reason: impl std::fmt::Display) -> Self {
+        Self {
+            status: 503,
+            body: format!("unavailable: {reason}"),
+        }
+    }
+}
+
+/// Liveness only reports that the process is able to serve requests.
+pub fn liveness() -> Health {
+    Health::ok()
+}
+
+/// Readiness additionally requires the repository to be usable.
+pub fn readiness(repository: &Repository) -> Health {
+    match repository.check() {
+        Ok(()) => Health::ok(),
+        Err(err) => {
+            tracing::warn!(%err, "repository failed readiness check");
+            Health::unavailable(err)
+        }
+    }
+}
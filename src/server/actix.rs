@@ -0,0 +1,389 @@
+use actix_files::{file_extension_to_mime, Files, NamedFile};
+use actix_service::IntoServiceFactory;
+use actix_web::{
+    body::{BoxBody, MessageBody},
+    dev::{self, Payload, Service, ServiceFactory, ServiceRequest, ServiceResponse},
+    http::{
+        header::{self, ContentEncoding, HeaderMap},
+        StatusCode,
+    },
+    middleware::{from_fn, Compress, DefaultHeaders, Next},
+    web, App, HttpResponse, Responder,
+};
+use futures::StreamExt;
+use leptos_actix::{generate_route_list, LeptosRoutes};
+use leptos_router::Method;
+use std::future::Future;
+use tracing_actix_web::TracingLogger;
+
+use super::{
+    api, assets,
+    csrf::{self, Check},
+    limits, security, shutdown, AppState,
+};
+use crate::{app::App as LeptosApp, auth, events, metrics};
+
+impl From<super::Health> for HttpResponse {
+    fn from(health: super::Health) -> Self {
+        let status =
+            StatusCode::from_u16(health.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        HttpResponse::build(status)
+            .content_type("text/plain; charset=utf-8")
+            .body(health.body)
+    }
+}
+
+impl From<super::Document> for HttpResponse {
+    fn from(document: super::Document) -> Self {
+        let status =
+            StatusCode::from_u16(document.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        HttpResponse::build(status)
+            .content_type(document.content_type)
+            .body(document.body)
+    }
+}
+
+async fn healthz() -> HttpResponse {
+    super::liveness().into()
+}
+
+async fn readyz(state: web::Data<AppState>) -> HttpResponse {
+    super::readiness(&state.repository).into()
+}
+
+async fn metrics() -> impl Responder {
+    HttpResponse::Ok()
+        .content_type(metrics::CONTENT_TYPE)
+        .body(metrics::render())
+}
+
+async fn feed(state: web::Data<AppState>) -> HttpResponse {
+    super::feed(&state).into()
+}
+
+async fn author_feed(state: web::Data<AppState>, name: web::Path<String>) -> HttpResponse {
+    super::author_feed(&state, &name).into()
+}
+
+async fn sitemap(state: web::Data<AppState>) -> HttpResponse {
+    super::sitemap(&state).into()
+}
+
+async fn robots(state: web::Data<AppState>) -> HttpResponse {
+    super::robots(&state).into()
+}
+
+async fn events(state: web::Data<AppState>) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type(events::CONTENT_TYPE)
+        .insert_header(("Cache-Control", "no-cache"))
+        // keeps `Compress` off the stream, as on the axum server
+        .insert_header(ContentEncoding::Identity)
+        .streaming(
+            state
+                .events
+                .stream()
+                .map(|frame| Ok::<_, actix_web::Error>(web::Bytes::from(frame))),
+        )
+}
+
+async fn api_authors(state: web::Data<AppState>) -> HttpResponse {
+    api::authors(&state.repository).into()
+}
+
+async fn api_author(state: web::Data<AppState>, name: web::Path<String>) -> HttpResponse {
+    api::author(&state.repository, &name).into()
+}
+
+async fn api_articles(
+    state: web::Data<AppState>,
+    query: web::Query<api::ArticlesQuery>,
+) -> HttpResponse {
+    api::articles(&state.repository, &query).into()
+}
+
+async fn api_article(state: web::Data<AppState>, id: web::Path<String>) -> HttpResponse {
+    api::article(&state.repository, &id).into()
+}
+
+async fn api_openapi() -> HttpResponse {
+    api::openapi().into()
+}
+
+/// The id in the session cookie of the request, if any.
+fn session(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get_all(header::COOKIE)
+        .filter_map(|value| value.to_str().ok())
+        .find_map(|value| auth::find_cookie(value, auth::COOKIE))
+}
+
+/// Turn away the calls to server functions over their rate limit or with
+/// a body over the size limit, reading the bodies of the rest into memory.
+async fn limits(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    let Some(state) = req.app_data::<web::Data<AppState>>().cloned() else {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    };
+    let headers = req.headers();
+    let check = limits::check(
+        &state,
+        &limits::Request {
+            method: req.method().as_str(),
+            path: req.path(),
+            ip: req.peer_addr().map(|addr| addr.ip()),
+            forwarded_for: headers
+                .get("x-forwarded-for")
+                .and_then(|value| value.to_str().ok()),
+            session: session(headers),
+            content_length: headers
+                .get(header::CONTENT_LENGTH)
+                .and_then(|value| value.to_str().ok()?.parse().ok()),
+        },
+    );
+    match check {
+        limits::Check::Allow => Ok(next.call(req).await?.map_into_boxed_body()),
+        limits::Check::Reject(document, retry_after) => {
+            let mut response = HttpResponse::from(document);
+            if let Some(seconds) = retry_after {
+                response
+                    .headers_mut()
+                    .insert(header::RETRY_AFTER, seconds.into());
+            }
+            Ok(req.into_response(response))
+        }
+        limits::Check::Body(max) => {
+            let body = match req.extract::<web::Bytes>().await {
+                Ok(body) if body.len() <= max => body,
+                _ => return Ok(req.into_response(HttpResponse::from(limits::too_large()))),
+            };
+            req.set_payload(Payload::from(body));
+            Ok(next.call(req).await?.map_into_boxed_body())
+        }
+    }
+}
+
+/// Reject the requests failing the CSRF check, reading the form of those
+/// that need their token checked into memory.
+async fn csrf(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    let Some(state) = req.app_data::<web::Data<AppState>>().cloned() else {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    };
+    let headers = req.headers();
+    let check = csrf::check(
+        &state,
+        &csrf::Request::new(
+            req.method().as_str(),
+            req.path(),
+            session(headers),
+            |name| headers.get(name).and_then(|value| value.to_str().ok()),
+        ),
+    );
+    match check {
+        Check::Allow => Ok(next.call(req).await?.map_into_boxed_body()),
+        Check::Reject(document) => Ok(req.into_response(HttpResponse::from(document))),
+        Check::Token(token) => {
+            let form = match req.extract::<web::Bytes>().await {
+                Ok(form) if form.len() <= state.config.limits.max_body_bytes => form,
+                _ => return Ok(req.into_response(HttpResponse::from(limits::too_large()))),
+            };
+            if let Err(document) = csrf::check_form(req.path(), &form, &token) {
+                return Ok(req.into_response(HttpResponse::from(document)));
+            }
+            req.set_payload(Payload::from(form));
+            Ok(next.call(req).await?.map_into_boxed_body())
+        }
+    }
+}
+
+/// Serve the precompressed variant of an asset accepted by the client in
+/// place of the asset, leaving the rest to `Files`.
+async fn precompressed(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    let found = req
+        .app_data::<web::Data<AppState>>()
+        .filter(|_| matches!(req.method().as_str(), "GET" | "HEAD"))
+        .and_then(|state| {
+            let accept_encoding = req.headers().get(header::ACCEPT_ENCODING)?.to_str().ok()?;
+            assets::precompressed(&state.leptos_options, req.path(), accept_encoding)
+        });
+    let Some((path, encoding)) = found else {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    };
+    let Ok(file) = NamedFile::open_async(&path).await else {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    };
+    let extension = req.path().rsplit('.').next().unwrap_or_default();
+    let file = file
+        .set_content_type(file_extension_to_mime(extension))
+        .set_content_encoding(match encoding {
+            assets::Encoding::Brotli => ContentEncoding::Brotli,
+            assets::Encoding::Gzip => ContentEncoding::Gzip,
+        });
+    let mut response = file.into_response(req.request());
+    response.headers_mut().append(
+        header::VARY,
+        header::HeaderValue::from_static("accept-encoding"),
+    );
+    Ok(req.into_response(response))
+}
+
+/// Add the caching headers to the successful responses not setting them
+/// themselves.
+async fn cache_headers(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    let state = req.app_data::<web::Data<AppState>>().cloned();
+    let path = req.path().to_string();
+    let mut res = next.call(req).await?.map_into_boxed_body();
+    let Some(state) = state else {
+        return Ok(res);
+    };
+    if res.status() != StatusCode::OK || res.headers().contains_key(header::CACHE_CONTROL) {
+        return Ok(res);
+    }
+    let content_type = res
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    for (name, value) in assets::headers(&state, &path, content_type.as_deref()) {
+        if let Ok(value) = header::HeaderValue::from_str(&value) {
+            res.headers_mut()
+                .append(header::HeaderName::from_static(name), value);
+        }
+    }
+    Ok(res)
+}
+
+/// The routes of the app as generated by `leptos_actix`.
+pub fn route_list() -> Vec<super::RouteInfo> {
+    generate_route_list(LeptosApp)
+        .into_iter()
+        .map(|listing| super::RouteInfo {
+            path: listing.path().to_string(),
+            mode: listing.mode().clone(),
+            methods: listing.methods().collect(),
+        })
+        .collect()
+}
+
+/// Render each page through the app, returning the status and body.
+pub async fn render_pages(
+    state: AppState,
+    pages: &[String],
+) -> std::io::Result<Vec<(u16, String)>> {
+    let service = app(state)
+        .into_factory()
+        .new_service(dev::AppConfig::default())
+        .await
+        .map_err(|()| std::io::Error::other("failed to start the app"))?;
+    let mut rendered = Vec::with_capacity(pages.len());
+    for page in pages {
+        let mut request = actix_http::Request::new();
+        request.head_mut().uri = page.parse().map_err(std::io::Error::other)?;
+        let response = service
+            .call(request)
+            .await
+            .map_err(|err| std::io::Error::other(err.to_string()))?;
+        let status = response.status().as_u16();
+        let body = actix_web::body::to_bytes(response.into_body())
+            .await
+            .map_err(|err| std::io::Error::other(err.into().to_string()))?;
+        rendered.push((status, String::from_utf8_lossy(&body).into_owned()));
+    }
+    Ok(rendered)
+}
+
+/// Build the complete app serving the Leptos app and everything else.
+pub fn app(
+    state: AppState,
+) -> App<
+    impl ServiceFactory<
+        ServiceRequest,
+        Config = (),
+        Response = ServiceResponse<impl MessageBody>,
+        Error = actix_web::Error,
+        InitError = (),
+    >,
+> {
+    let routes = generate_route_list(LeptosApp);
+    let site_root = state.leptos_options.site_root.clone();
+    let max_body_bytes = state.config.limits.max_body_bytes;
+    let security_headers = security::headers(&state.config.security)
+        .into_iter()
+        .fold(DefaultHeaders::new(), |headers, header| headers.add(header));
+
+    App::new()
+        .app_data(web::Data::new(state.clone()))
+        // lets the middleware read bodies of up to the limit
+        .app_data(web::PayloadConfig::new(max_body_bytes))
+        .route("/healthz", web::get().to(healthz))
+        .route("/readyz", web::get().to(readyz))
+        .route("/metrics", web::get().to(metrics))
+        .route("/feed.xml", web::get().to(feed))
+        .route("/author/{name}/feed.xml", web::get().to(author_feed))
+        .route("/sitemap.xml", web::get().to(sitemap))
+        .route("/robots.txt", web::get().to(robots))
+        .route("/api/v1/authors", web::get().to(api_authors))
+        .route("/api/v1/authors/{name}", web::get().to(api_author))
+        .route("/api/v1/articles", web::get().to(api_articles))
+        .route("/api/v1/articles/{id}", web::get().to(api_article))
+        .route("/api/v1/openapi.json", web::get().to(api_openapi))
+        .route(events::PATH, web::get().to(events))
+        .leptos_routes_with_context(
+            routes,
+            {
+                let state = state.clone();
+                move || state.provide_contexts()
+            },
+            {
+                let options = state.leptos_options.clone();
+                move || super::render(options.clone())
+            },
+        )
+        // the app renders its 404 page for paths that are neither routes
+        // nor files
+        .service(Files::new("/", site_root.as_ref()).default_handler(
+            leptos_actix::render_app_to_stream_with_context(
+                {
+                    let state = state.clone();
+                    move || state.provide_contexts()
+                },
+                move || super::render(state.leptos_options.clone()),
+                Method::Get,
+            ),
+        ))
+        .wrap(from_fn(precompressed))
+        .wrap(from_fn(csrf))
+        .wrap(from_fn(limits))
+        .wrap(security_headers)
+        .wrap(from_fn(cache_headers))
+        .wrap(Compress::default())
+        .wrap(TracingLogger::default())
+}
+
+/// Run the server until `stop` resolves, such as `shutdown::signal`, after
+/// which shutdown is requested, ending the event streams, and the workers
+/// stop once their in-flight responses complete or the shutdown timeout
+/// elapses.  The server must have its own signal handling disabled.
+pub async fn serve(
+    server: dev::Server,
+    stop: impl Future<Output = ()> + 'static,
+) -> std::io::Result<()> {
+    let handle = server.handle();
+    actix_web::rt::spawn(async move {
+        stop.await;
+        shutdown::request();
+        handle.stop(true).await;
+    });
+    server.await
+}
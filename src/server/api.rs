@@ -0,0 +1,193 @@
+//! A JSON REST API over the repository for other services, mirroring the
+//! read server functions without the server function encoding, along
+//! with its OpenAPI document.
+
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use utoipa::{IntoParams, OpenApi, ToSchema};
+
+use super::Document;
+use crate::{
+    app::{server::articles_by_author, Article, Author},
+    error::AppError,
+    repository::Repository,
+};
+
+pub const CONTENT_TYPE: &str = "application/json";
+
+#[derive(Serialize, ToSchema)]
+pub struct AuthorEntry {
+    /// The name identifying the author in URLs.
+    pub id: String,
+    pub name: String,
+    pub email: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ArticleEntry {
+    pub id: u32,
+    /// The id of the author of the article.
+    pub author: String,
+    pub title: String,
+}
+
+/// The body of every unsuccessful response.
+#[derive(Serialize, ToSchema)]
+pub struct ApiError {
+    pub error: String,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct ArticlesQuery {
+    /// Only list the articles by the author with this id.
+    pub author: Option<String>,
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Portlet demo content API"),
+    paths(authors, author, articles, article)
+)]
+pub struct ApiDoc;
+
+impl From<(String, Author)> for AuthorEntry {
+    fn from((id, author): (String, Author)) -> Self {
+        Self {
+            id,
+            name: author.name,
+            email: author.email,
+        }
+    }
+}
+
+impl From<(u32, Article)> for ArticleEntry {
+    fn from((id, article): (u32, Article)) -> Self {
+        Self {
+            id,
+            author: article.author_name,
+            title: article.title,
+        }
+    }
+}
+
+fn respond<T: Serialize>(result: Result<T, AppError>) -> Document {
+    let (status, body) = match result {
+        Ok(body) => (200, serde_json::to_string(&body)),
+        Err(error) => {
+            if let AppError::Internal(reason) = &error {
+                tracing::error!(%reason, "api request failed");
+            }
+            let body = ApiError {
+                error: error.message(),
+            };
+            (error.status(), serde_json::to_string(&body))
+        }
+    };
+    Document {
+        status,
+        content_type: CONTENT_TYPE,
+        body: body.expect("api responses must serialize"),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/authors",
+    responses((status = 200, description = "All authors", body = [AuthorEntry]))
+)]
+pub fn authors(repository: &Repository) -> Document {
+    respond(
+        repository
+            .list_authors()
+            .map(|authors| {
+                authors
+                    .into_iter()
+                    .map(AuthorEntry::from)
+                    .collect::<Vec<_>>()
+            })
+            .map_err(AppError::from),
+    )
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/authors/{name}",
+    params(("name" = String, Path, description = "The id of the author")),
+    responses(
+        (status = 200, description = "The author", body = AuthorEntry),
+        (status = 404, description = "No such author", body = ApiError),
+    )
+)]
+pub fn author(repository: &Repository, name: &str) -> Document {
+    respond(
+        repository
+            .get_author(name)
+            .map_err(AppError::from)
+            .and_then(|author| {
+                author
+                    .map(AuthorEntry::from)
+                    .ok_or(AppError::NotFound(format!("author {name}")))
+            }),
+    )
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/articles",
+    params(ArticlesQuery),
+    responses(
+        (status = 200, description = "The articles", body = [ArticleEntry]),
+        (status = 404, description = "No such author", body = ApiError),
+    )
+)]
+pub fn articles(repository: &Repository, query: &ArticlesQuery) -> Document {
+    let articles = match &query.author {
+        None => repository.list_articles().map_err(AppError::from),
+        Some(name) => articles_by_author(repository, name),
+    };
+    respond(articles.map(|articles| {
+        articles
+            .into_iter()
+            .map(ArticleEntry::from)
+            .collect::<Vec<_>>()
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/articles/{id}",
+    params(("id" = u32, Path, description = "The id of the article")),
+    responses(
+        (status = 200, description = "The article", body = ArticleEntry),
+        (status = 400, description = "Malformed id", body = ApiError),
+        (status = 404, description = "No such article", body = ApiError),
+    )
+)]
+pub fn article(repository: &Repository, id: &str) -> Document {
+    let article = id
+        .parse::<u32>()
+        .map_err(|_| AppError::BadParam("article id".to_string()))
+        .and_then(|id| {
+            repository
+                .get_article(id)?
+                .map(|article| ArticleEntry::from((id, article)))
+                .ok_or(AppError::NotFound(format!("article {id}")))
+        });
+    respond(article)
+}
+
+/// The OpenAPI document of the API, generated once from the types.
+pub fn openapi() -> Document {
+    static DOCUMENT: OnceLock<String> = OnceLock::new();
+    Document {
+        status: 200,
+        content_type: CONTENT_TYPE,
+        body: DOCUMENT
+            .get_or_init(|| {
+                ApiDoc::openapi()
+                    .to_pretty_json()
+                    .expect("the api document must serialize")
+            })
+            .clone(),
+    }
+}
@@ -0,0 +1,132 @@
+//! How long browsers may cache the responses of both servers, and the
+//! precompressed variants of the assets under the site's `pkg` directory.
+//! Pages are revalidated on every use unless `http_cache.html_max_age_secs`
+//! is set, and then cached privately as they differ by login; assets whose
+//! names carry the hash of their content are cached for a year, and other
+//! assets for `http_cache.asset_max_age_secs`.
+
+use leptos::prelude::LeptosOptions;
+use std::path::PathBuf;
+
+use super::AppState;
+
+/// The `Cache-Control` of assets whose names change with their content.
+pub const IMMUTABLE: &str = "public, max-age=31536000, immutable";
+
+/// The headers controlling the caching of a successful response to the
+/// path with the content type, for responses not setting them already.
+pub fn headers(
+    state: &AppState,
+    path: &str,
+    content_type: Option<&str>,
+) -> Vec<(&'static str, String)> {
+    let config = &state.config.http_cache;
+    if content_type.is_some_and(|content_type| content_type.starts_with("text/html")) {
+        let cache_control = match config.html_max_age_secs {
+            0 => "no-cache".to_string(),
+            secs => format!("private, max-age={secs}"),
+        };
+        return vec![
+            ("cache-control", cache_control),
+            ("vary", "cookie".to_string()),
+        ];
+    }
+    let Some(file) = asset(&state.leptos_options, path) else {
+        return Vec::new();
+    };
+    let cache_control = if is_hashed(file) {
+        IMMUTABLE.to_string()
+    } else {
+        format!("public, max-age={}", config.asset_max_age_secs)
+    };
+    vec![("cache-control", cache_control)]
+}
+
+/// A compression of a file stored next to it, such as `app.wasm.br`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    Brotli,
+    Gzip,
+}
+
+impl Encoding {
+    /// The name of the encoding in `Accept-Encoding`.
+    fn name(self) -> &'static str {
+        match self {
+            Encoding::Brotli => "br",
+            Encoding::Gzip => "gzip",
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Encoding::Brotli => "br",
+            Encoding::Gzip => "gz",
+        }
+    }
+}
+
+/// The precompressed variant of the asset at the path accepted by the
+/// client, preferring brotli, if there is one on disk.
+pub fn precompressed(
+    options: &LeptosOptions,
+    path: &str,
+    accept_encoding: &str,
+) -> Option<(PathBuf, Encoding)> {
+    let file = asset(options, path)?;
+    // anything that could escape the directory is left to the file service
+    let segments = file.split('/');
+    if segments
+        .clone()
+        .any(|segment| matches!(segment, "" | "." | "..") || segment.contains(['%', '\\']))
+    {
+        return None;
+    }
+    let asset = segments.fold(
+        PathBuf::from(&*options.site_root).join(&*options.site_pkg_dir),
+        |path, segment| path.join(segment),
+    );
+    [Encoding::Brotli, Encoding::Gzip]
+        .into_iter()
+        .filter(|encoding| accepts(accept_encoding, encoding.name()))
+        .map(|encoding| {
+            let mut compressed = asset.clone().into_os_string();
+            compressed.push(".");
+            compressed.push(encoding.extension());
+            (PathBuf::from(compressed), encoding)
+        })
+        .find(|(compressed, _)| compressed.is_file())
+}
+
+/// The path of the asset within the `pkg` directory, if the path is in it.
+fn asset<'a>(options: &LeptosOptions, path: &'a str) -> Option<&'a str> {
+    path.strip_prefix('/')?
+        .strip_prefix(&*options.site_pkg_dir)?
+        .strip_prefix('/')
+        .filter(|file| !file.is_empty())
+}
+
+/// Whether the name of the file carries a hash, as in the
+/// `app.0123abcd.wasm` written by `cargo leptos` with `hash-files`.
+fn is_hashed(file: &str) -> bool {
+    let name = file.rsplit('/').next().unwrap_or(file);
+    let parts: Vec<_> = name.split('.').collect();
+    parts.len() >= 3
+        && parts[parts.len() - 2].len() >= 8
+        && parts[parts.len() - 2]
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Whether the `Accept-Encoding` allows the encoding.
+fn accepts(accept_encoding: &str, name: &str) -> bool {
+    accept_encoding.split(',').any(|item| {
+        let mut params = item.split(';').map(str::trim);
+        params
+            .next()
+            .is_some_and(|coding| coding.eq_ignore_ascii_case(name))
+            && params
+                .filter_map(|param| param.strip_prefix("q="))
+                .all(|q| q.parse::<f32>().is_ok_and(|q| q > 0.0))
+    })
+}
@@ -0,0 +1,376 @@
+use axum::{
+    body::{to_bytes, Body},
+    extract::{ConnectInfo, Path, Query, State},
+    handler::Handler,
+    http::{header, Request, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use futures::StreamExt;
+use leptos::prelude::*;
+use leptos_axum::{generate_route_list, LeptosRoutes};
+use std::{
+    convert::Infallible,
+    future::{Future, IntoFuture},
+    io,
+    net::SocketAddr,
+    sync::Arc,
+    time::Duration,
+};
+use tokio::{net::TcpListener, sync::watch};
+use tower::ServiceExt;
+use tower_http::{compression::CompressionLayer, services::ServeDir, trace::TraceLayer};
+
+use super::{
+    api, assets,
+    csrf::{self, Check},
+    limits, security, shutdown, AppState,
+};
+use crate::{
+    app::{shell, App},
+    auth, events, metrics,
+};
+
+impl axum::extract::FromRef<AppState> for LeptosOptions {
+    fn from_ref(state: &AppState) -> Self {
+        state.leptos_options.clone()
+    }
+}
+
+impl IntoResponse for super::Health {
+    fn into_response(self) -> axum::response::Response {
+        let status = StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        (status, self.body).into_response()
+    }
+}
+
+impl IntoResponse for super::Document {
+    fn into_response(self) -> axum::response::Response {
+        let status = StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        (
+            status,
+            [(header::CONTENT_TYPE, self.content_type)],
+            self.body,
+        )
+            .into_response()
+    }
+}
+
+async fn healthz() -> super::Health {
+    super::liveness()
+}
+
+async fn readyz(State(state): State<AppState>) -> super::Health {
+    super::readiness(&state.repository)
+}
+
+async fn metrics() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, metrics::CONTENT_TYPE)],
+        metrics::render(),
+    )
+}
+
+async fn feed(State(state): State<AppState>) -> super::Document {
+    super::feed(&state)
+}
+
+async fn author_feed(State(state): State<AppState>, Path(name): Path<String>) -> super::Document {
+    super::author_feed(&state, &name)
+}
+
+async fn sitemap(State(state): State<AppState>) -> super::Document {
+    super::sitemap(&state)
+}
+
+async fn robots(State(state): State<AppState>) -> super::Document {
+    super::robots(&state)
+}
+
+async fn events(State(state): State<AppState>) -> impl IntoResponse {
+    (
+        [
+            (header::CONTENT_TYPE, events::CONTENT_TYPE),
+            (header::CACHE_CONTROL, "no-cache"),
+        ],
+        Body::from_stream(state.events.stream().map(Ok::<_, Infallible>)),
+    )
+}
+
+async fn api_authors(State(state): State<AppState>) -> super::Document {
+    api::authors(&state.repository)
+}
+
+async fn api_author(State(state): State<AppState>, Path(name): Path<String>) -> super::Document {
+    api::author(&state.repository, &name)
+}
+
+async fn api_articles(
+    State(state): State<AppState>,
+    Query(query): Query<api::ArticlesQuery>,
+) -> super::Document {
+    api::articles(&state.repository, &query)
+}
+
+async fn api_article(State(state): State<AppState>, Path(id): Path<String>) -> super::Document {
+    api::article(&state.repository, &id)
+}
+
+async fn api_openapi() -> super::Document {
+    api::openapi()
+}
+
+/// The id in the session cookie of the request, if any.
+fn session(headers: &header::HeaderMap) -> Option<&str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .find_map(|value| auth::find_cookie(value, auth::COOKIE))
+}
+
+/// Turn away the calls to server functions over their rate limit or with
+/// a body over the size limit, reading the bodies of the rest into memory.
+async fn limits(State(state): State<AppState>, request: Request<Body>, next: Next) -> Response {
+    let headers = request.headers();
+    let check = limits::check(
+        &state,
+        &limits::Request {
+            method: request.method().as_str(),
+            path: request.uri().path(),
+            ip: request
+                .extensions()
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| addr.ip()),
+            forwarded_for: headers
+                .get("x-forwarded-for")
+                .and_then(|value| value.to_str().ok()),
+            session: session(headers),
+            content_length: headers
+                .get(header::CONTENT_LENGTH)
+                .and_then(|value| value.to_str().ok()?.parse().ok()),
+        },
+    );
+    match check {
+        limits::Check::Allow => next.run(request).await,
+        limits::Check::Reject(document, retry_after) => {
+            let mut response = document.into_response();
+            if let Some(seconds) = retry_after {
+                response
+                    .headers_mut()
+                    .insert(header::RETRY_AFTER, seconds.into());
+            }
+            response
+        }
+        limits::Check::Body(max) => {
+            let (parts, body) = request.into_parts();
+            let Ok(body) = to_bytes(body, max).await else {
+                return limits::too_large().into_response();
+            };
+            next.run(Request::from_parts(parts, Body::from(body))).await
+        }
+    }
+}
+
+/// Reject the requests failing the CSRF check, reading the form of those
+/// that need their token checked into memory.
+async fn csrf(State(state): State<AppState>, request: Request<Body>, next: Next) -> Response {
+    let headers = request.headers();
+    let check = csrf::check(
+        &state,
+        &csrf::Request::new(
+            request.method().as_str(),
+            request.uri().path(),
+            session(headers),
+            |name| headers.get(name).and_then(|value| value.to_str().ok()),
+        ),
+    );
+    match check {
+        Check::Allow => next.run(request).await,
+        Check::Reject(document) => document.into_response(),
+        Check::Token(token) => {
+            let (parts, body) = request.into_parts();
+            let Ok(form) = to_bytes(body, state.config.limits.max_body_bytes).await else {
+                return limits::too_large().into_response();
+            };
+            if let Err(document) = csrf::check_form(parts.uri.path(), &form, &token) {
+                return document.into_response();
+            }
+            next.run(Request::from_parts(parts, Body::from(form))).await
+        }
+    }
+}
+
+/// Add the security headers, built once by `router`, to every response
+/// not setting them itself.
+async fn security_headers(
+    State(security_headers): State<Arc<header::HeaderMap>>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+    for (name, value) in security_headers.iter() {
+        headers.entry(name).or_insert_with(|| value.clone());
+    }
+    response
+}
+
+/// Add the caching headers to the successful responses not setting them
+/// themselves.
+async fn cache_headers(
+    State(state): State<AppState>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let path = request.uri().path().to_string();
+    let mut response = next.run(request).await;
+    if response.status() != StatusCode::OK || response.headers().contains_key(header::CACHE_CONTROL)
+    {
+        return response;
+    }
+    let headers = response.headers_mut();
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok());
+    for (name, value) in assets::headers(&state, &path, content_type) {
+        if let Ok(value) = header::HeaderValue::from_str(&value) {
+            headers.append(name, value);
+        }
+    }
+    response
+}
+
+/// The routes of the app as generated by `leptos_axum`.
+pub fn route_list() -> Vec<super::RouteInfo> {
+    generate_route_list(App)
+        .into_iter()
+        .map(|listing| super::RouteInfo {
+            path: listing.path().to_string(),
+            mode: listing.mode().clone(),
+            methods: listing.methods().collect(),
+        })
+        .collect()
+}
+
+/// Render each page through the router, returning the status and body.
+pub async fn render_pages(state: AppState, pages: &[String]) -> io::Result<Vec<(u16, String)>> {
+    let router = router(state);
+    let mut rendered = Vec::with_capacity(pages.len());
+    for page in pages {
+        let request = Request::get(page)
+            .body(Body::empty())
+            .map_err(io::Error::other)?;
+        let Ok(response) = router.clone().oneshot(request).await;
+        let status = response.status().as_u16();
+        let body = to_bytes(response.into_body(), usize::MAX)
+            .await
+            .map_err(io::Error::other)?;
+        rendered.push((status, String::from_utf8_lossy(&body).into_owned()));
+    }
+    Ok(rendered)
+}
+
+/// Build the complete router serving the Leptos app and everything else.
+pub fn router(state: AppState) -> Router {
+    // Generate the list of routes in your Leptos App
+    let routes = generate_route_list(App);
+    let headers: header::HeaderMap = security::headers(&state.config.security)
+        .into_iter()
+        .map(|(name, value)| {
+            let value = header::HeaderValue::from_str(&value)
+                .expect("header values are checked with the configuration");
+            (header::HeaderName::from_static(name), value)
+        })
+        .collect();
+    // the shell rendering a 404 for paths that are neither routes nor files
+    let not_found = leptos_axum::file_and_error_handler_with_context::<AppState, _>(
+        {
+            let state = state.clone();
+            move || state.provide_contexts()
+        },
+        shell,
+    );
+    let files = ServeDir::new(&*state.leptos_options.site_root)
+        .precompressed_br()
+        .precompressed_gzip()
+        .call_fallback_on_method_not_allowed(true)
+        .fallback(not_found.with_state(state.clone()));
+
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .route("/metrics", get(metrics))
+        .route("/feed.xml", get(feed))
+        .route("/author/{name}/feed.xml", get(author_feed))
+        .route("/sitemap.xml", get(sitemap))
+        .route("/robots.txt", get(robots))
+        .route("/api/v1/authors", get(api_authors))
+        .route("/api/v1/authors/{name}", get(api_author))
+        .route("/api/v1/articles", get(api_articles))
+        .route("/api/v1/articles/{id}", get(api_article))
+        .route("/api/v1/openapi.json", get(api_openapi))
+        .route(events::PATH, get(events))
+        .leptos_routes_with_context(
+            &state,
+            routes,
+            {
+                let state = state.clone();
+                move || state.provide_contexts()
+            },
+            {
+                let leptos_options = state.leptos_options.clone();
+                move || super::render(leptos_options.clone())
+            },
+        )
+        .fallback_service(files)
+        .layer(middleware::from_fn_with_state(state.clone(), csrf))
+        .layer(middleware::from_fn_with_state(state.clone(), limits))
+        .layer(middleware::from_fn_with_state(
+            Arc::new(headers),
+            security_headers,
+        ))
+        .layer(middleware::from_fn_with_state(state.clone(), cache_headers))
+        .layer(CompressionLayer::new())
+        .layer(TraceLayer::new_for_http())
+        .with_state(state)
+}
+
+/// Serve the router until `stop` resolves, such as `shutdown::signal`,
+/// after which no new connections are accepted and in-flight responses
+/// are given up to the grace period to complete.
+pub async fn serve(
+    listener: TcpListener,
+    router: Router,
+    grace_period: Duration,
+    stop: impl Future<Output = ()> + Send + 'static,
+) -> std::io::Result<()> {
+    let (tx, mut rx) = watch::channel(false);
+    let server = axum::serve(
+        listener,
+        router.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(async move {
+        stop.await;
+        shutdown::request();
+        let _ = tx.send(true);
+    })
+    .into_future();
+    let grace_period = async move {
+        let _ = rx.wait_for(|requested| *requested).await;
+        tokio::time::sleep(grace_period).await;
+    };
+
+    tokio::select! {
+        result = server => result,
+        _ = grace_period => {
+            tracing::warn!(
+                in_flight = shutdown::in_flight(),
+                "grace period elapsed, abandoning in-flight responses"
+            );
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,124 @@
+//! Protection against cross-site request forgery, applied by both servers
+//! before any handler runs.  Every request other than a read must come
+//! from the site itself, going by its `Origin` or else `Referer` header
+//! when it has either, and requests to the server functions writing
+//! content must also carry the CSRF token of their session, if they have
+//! one, in the header added by `CsrfClient` or else the form field added
+//! by `CsrfField`.
+
+use super::{AppState, Document, WRITES};
+use crate::auth::{CSRF_FIELD, CSRF_HEADER};
+
+/// The parts of a request the check depends on.
+pub struct Request<'a> {
+    pub method: &'a str,
+    pub path: &'a str,
+    pub host: Option<&'a str>,
+    pub origin: Option<&'a str>,
+    pub referer: Option<&'a str>,
+    /// The id in the session cookie, if any.
+    pub session: Option<&'a str>,
+    /// The token in the `CSRF_HEADER`, if any.
+    pub token: Option<&'a str>,
+}
+
+impl<'a> Request<'a> {
+    /// The request with the method, path and session, taking the rest
+    /// from its headers as found by `header`.
+    pub fn new(
+        method: &'a str,
+        path: &'a str,
+        session: Option<&'a str>,
+        header: impl Fn(&str) -> Option<&'a str>,
+    ) -> Self {
+        Self {
+            method,
+            path,
+            host: header("host"),
+            origin: header("origin"),
+            referer: header("referer"),
+            session,
+            token: header(CSRF_HEADER),
+        }
+    }
+}
+
+/// What is to become of a request.
+pub enum Check {
+    Allow,
+    /// Allow the request if its form carries the token, checked by
+    /// `check_form`.
+    Token(String),
+    Reject(Document),
+}
+
+pub fn check(state: &AppState, request: &Request) -> Check {
+    if matches!(request.method, "GET" | "HEAD" | "OPTIONS") {
+        return Check::Allow;
+    }
+    if let Some(source) = request.origin.or(request.referer) {
+        let from = host(source);
+        let ours = [request.host, host(&state.config.public_url)];
+        if from.is_none() || !ours.contains(&from) {
+            tracing::warn!(
+                path = request.path,
+                source,
+                "rejecting cross-origin request"
+            );
+            return Check::Reject(rejected("cross-origin request"));
+        }
+    }
+    if !WRITES.contains(&(request.path, true)) {
+        return Check::Allow;
+    }
+    // without a session there is no login for a forged request to abuse
+    let Some(token) = request.session.and_then(|id| state.sessions.csrf_token(id)) else {
+        return Check::Allow;
+    };
+    match request.token {
+        Some(sent) if constant_time_eq(sent.as_bytes(), token.as_bytes()) => Check::Allow,
+        Some(_) => Check::Reject(missing_token(request.path)),
+        None => Check::Token(token),
+    }
+}
+
+/// Check the url-encoded form of a request found to need the token.
+pub fn check_form(path: &str, form: &[u8], token: &str) -> Result<(), Document> {
+    let found = form_urlencoded::parse(form)
+        .find(|(key, _)| key == CSRF_FIELD)
+        .is_some_and(|(_, value)| constant_time_eq(value.as_bytes(), token.as_bytes()));
+    if found {
+        Ok(())
+    } else {
+        Err(missing_token(path))
+    }
+}
+
+fn missing_token(path: &str) -> Document {
+    tracing::warn!(path, "rejecting request without CSRF token");
+    rejected("missing or invalid CSRF token")
+}
+
+/// The response to a request failing the check.
+pub fn rejected(reason: &str) -> Document {
+    Document {
+        status: 403,
+        content_type: Document::TEXT,
+        body: format!("forbidden: {reason}"),
+    }
+}
+
+/// The host and port of an origin or URL, such as `example.com:8080`;
+/// `None` for the opaque origin `null`.
+fn host(url: &str) -> Option<&str> {
+    let (_, rest) = url.split_once("://")?;
+    rest.split(['/', '?', '#'])
+        .next()
+        .filter(|host| !host.is_empty())
+}
+
+/// Compare without returning early, so that the time taken does not
+/// reveal how much of the token was guessed right.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
@@ -0,0 +1,85 @@
+//! RSS feeds of the articles, built from the same repository listings as
+//! the `list_articles` and `list_articles_by_author` server functions.
+
+use crate::{
+    app::Article,
+    config::AppConfig,
+    repository::{Repository, RepositoryError},
+};
+
+pub const CONTENT_TYPE: &str = "application/rss+xml; charset=utf-8";
+
+/// The feed of the latest articles by everyone.
+pub fn articles(repository: &Repository, config: &AppConfig) -> Result<String, RepositoryError> {
+    Ok(render(
+        config,
+        "All articles",
+        "/article/",
+        "/feed.xml",
+        repository.list_articles()?,
+    ))
+}
+
+/// The feed of the latest articles by a single author, if there is one by
+/// that name.
+pub fn articles_by_author(
+    repository: &Repository,
+    config: &AppConfig,
+    name: &str,
+) -> Result<Option<String>, RepositoryError> {
+    let Some((id, author)) = repository.get_author(name)? else {
+        return Ok(None);
+    };
+    Ok(Some(render(
+        config,
+        &format!("Articles by {}", author.name),
+        &format!("/author/{id}/articles"),
+        &format!("/author/{id}/feed.xml"),
+        repository.list_articles_by_author(&id)?,
+    )))
+}
+
+/// Render the newest page worth of articles as an RSS 2.0 channel; there
+/// are no publication dates, so the newest articles are those with the
+/// highest ids.
+fn render(
+    config: &AppConfig,
+    title: &str,
+    link: &str,
+    feed: &str,
+    articles: Vec<(u32, Article)>,
+) -> String {
+    let base = config.public_url.trim_end_matches('/');
+    let items = articles
+        .into_iter()
+        .rev()
+        .take(config.page_size)
+        .map(|(id, article)| {
+            format!(
+                "    <item>\n      <title>{}</title>\n      <link>{base}/article/{id}/</link>\n      <guid>{base}/article/{id}/</guid>\n    </item>\n",
+                escape(&article.title),
+            )
+        })
+        .collect::<String>();
+    format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<rss version="2.0" xmlns:atom="http://www.w3.org/2005/Atom">
+  <channel>
+    <title>{title}</title>
+    <link>{base}{link}</link>
+    <description>{title}</description>
+    <atom:link href="{base}{feed}" rel="self" type="application/rss+xml"/>
+{items}  </channel>
+</rss>
+"#,
+        title = escape(title),
+    )
+}
+
+pub(super) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
@@ -0,0 +1,254 @@
+//! Rate and size limits on the requests to the server functions, applied
+//! by both servers before the CSRF check.  Calls are counted against the
+//! client IP and, for a logged in client, its session as well, in token
+//! buckets refilling at the configured rate a minute; a call finding
+//! either bucket empty is turned away with a 429 and a `Retry-After` of
+//! the seconds until the bucket has a token again.  The client IP is that
+//! of the connection unless it is one of `limits.trusted_proxies`, in
+//! which case it is taken from the `X-Forwarded-For` the proxy appended.
+
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use super::{AppState, Document, WRITES};
+use crate::{config::LimitsConfig, metrics};
+
+/// The prefix of the paths of the server functions.
+const PREFIX: &str = "/api/";
+
+/// The kind of server function called, each limited at its own rate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Class {
+    Read,
+    Write,
+}
+
+impl Class {
+    /// The class of the server function the request calls, or `None`
+    /// when it calls none.
+    pub fn of(method: &str, path: &str) -> Option<Self> {
+        if method != "POST" || !path.starts_with(PREFIX) {
+            return None;
+        }
+        Some(if WRITES.iter().any(|&(write, _)| write == path) {
+            Class::Write
+        } else {
+            Class::Read
+        })
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Class::Read => "read",
+            Class::Write => "write",
+        }
+    }
+}
+
+/// Who a call is counted against.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum Client {
+    Ip(IpAddr),
+    Session(String),
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// How long a bucket takes to fill up again, after which it is no
+/// different from one never created.
+const FULL_AFTER: Duration = Duration::from_secs(60);
+
+/// The buckets of the clients, along with when those full again were
+/// last dropped.
+struct Buckets {
+    buckets: HashMap<(Class, Client), Bucket>,
+    swept: Instant,
+}
+
+impl Default for Buckets {
+    fn default() -> Self {
+        Self {
+            buckets: HashMap::new(),
+            swept: Instant::now(),
+        }
+    }
+}
+
+impl Buckets {
+    /// Drop the buckets full again, at most once every `FULL_AFTER` so
+    /// that the calls do not each pay for a pass over all the clients.
+    fn sweep(&mut self, now: Instant) {
+        if now - self.swept < FULL_AFTER {
+            return;
+        }
+        self.buckets
+            .retain(|_, bucket| now - bucket.updated < FULL_AFTER);
+        self.swept = now;
+    }
+}
+
+/// The token buckets of the clients, shared by all requests.
+#[derive(Clone, Default)]
+pub struct RateLimiter {
+    buckets: Arc<Mutex<Buckets>>,
+    limits: LimitsConfig,
+}
+
+impl RateLimiter {
+    pub fn new(limits: LimitsConfig) -> Self {
+        Self {
+            buckets: Default::default(),
+            limits,
+        }
+    }
+
+    /// Take a token from the buckets of the IP and the session, if any,
+    /// or return how long until every one of them has a token again.
+    fn take(&self, class: Class, clients: &[Client]) -> Result<(), Duration> {
+        let per_minute = match class {
+            Class::Read => self.limits.reads_per_minute,
+            Class::Write => self.limits.writes_per_minute,
+        };
+        if per_minute == 0 {
+            return Ok(());
+        }
+        let capacity = f64::from(per_minute);
+        let per_second = capacity / 60.0;
+        let Ok(mut buckets) = self.buckets.lock() else {
+            return Ok(());
+        };
+        let now = Instant::now();
+        buckets.sweep(now);
+        let buckets = &mut buckets.buckets;
+
+        let mut wait = Duration::ZERO;
+        for client in clients {
+            let bucket = buckets.entry((class, client.clone())).or_insert(Bucket {
+                tokens: capacity,
+                updated: now,
+            });
+            let elapsed = (now - bucket.updated).as_secs_f64();
+            bucket.tokens = (bucket.tokens + elapsed * per_second).min(capacity);
+            bucket.updated = now;
+            if bucket.tokens < 1.0 {
+                wait = wait.max(Duration::from_secs_f64((1.0 - bucket.tokens) / per_second));
+            }
+        }
+        if !wait.is_zero() {
+            return Err(wait);
+        }
+        for client in clients {
+            if let Some(bucket) = buckets.get_mut(&(class, client.clone())) {
+                bucket.tokens -= 1.0;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The parts of a request the limits depend on.
+pub struct Request<'a> {
+    pub method: &'a str,
+    pub path: &'a str,
+    /// The address of the peer, if known.
+    pub ip: Option<IpAddr>,
+    /// The `X-Forwarded-For` of the request, if any.
+    pub forwarded_for: Option<&'a str>,
+    /// The id in the session cookie, if any.
+    pub session: Option<&'a str>,
+    /// The `Content-Length` of the body, if given.
+    pub content_length: Option<usize>,
+}
+
+/// What is to become of a request.
+pub enum Check {
+    /// Allow a request to none of the server functions.
+    Allow,
+    /// Allow the call to a server function if its body, read in full,
+    /// is no larger than the limit.
+    Body(usize),
+    /// Reject the call, with the seconds after which to try again for
+    /// one over the rate limit.
+    Reject(Document, Option<u64>),
+}
+
+pub fn check(state: &AppState, request: &Request) -> Check {
+    let Some(class) = Class::of(request.method, request.path) else {
+        return Check::Allow;
+    };
+    let max = state.config.limits.max_body_bytes;
+    if request.content_length.is_some_and(|length| length > max) {
+        return Check::Reject(too_large(), None);
+    }
+    // sessions that do not exist are not worth the memory of a bucket
+    let session = request
+        .session
+        .filter(|id| state.sessions.get(id).is_some())
+        .map(|id| Client::Session(id.to_string()));
+    let ip = request
+        .ip
+        .map(|ip| client_ip(&state.config.limits, ip, request.forwarded_for));
+    let clients: Vec<_> = ip.map(Client::Ip).into_iter().chain(session).collect();
+    match state.limiter.take(class, &clients) {
+        Ok(()) => Check::Body(max),
+        Err(wait) => {
+            metrics::record_rate_limited(class.name());
+            tracing::warn!(
+                path = request.path,
+                ?ip,
+                class = class.name(),
+                "rejecting request over the rate limit"
+            );
+            let retry_after = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+            Check::Reject(too_many(), Some(retry_after))
+        }
+    }
+}
+
+/// The IP of the client connected from the peer, which is that of the
+/// peer itself unless it is a trusted proxy.  The addresses appended to
+/// `X-Forwarded-For` by the trusted proxies are then skipped from the
+/// right, as any before them could have been sent by the client.
+fn client_ip(limits: &LimitsConfig, peer: IpAddr, forwarded_for: Option<&str>) -> IpAddr {
+    let trusted = |ip: &IpAddr| limits.trusted_proxies.contains(ip);
+    if !trusted(&peer) {
+        return peer;
+    }
+    let mut client = peer;
+    for hop in forwarded_for.unwrap_or_default().rsplit(',') {
+        let Ok(ip) = hop.trim().parse() else {
+            break;
+        };
+        client = ip;
+        if !trusted(&ip) {
+            break;
+        }
+    }
+    client
+}
+
+/// The response to a request over the rate limit, to be sent with a
+/// `Retry-After` header.
+pub fn too_many() -> Document {
+    Document {
+        status: 429,
+        content_type: Document::TEXT,
+        body: "too many requests".to_string(),
+    }
+}
+
+/// The response to a request whose body is over the size limit.
+pub fn too_large() -> Document {
+    Document {
+        status: 413,
+        content_type: Document::TEXT,
+        body: "payload too large".to_string(),
+    }
+}
@@ -0,0 +1,65 @@
+//! The security headers sent by both servers: the `Content-Security-Policy`
+//! of each page, allowing the inline scripts rendered with the nonce of
+//! the response, and the headers added to every response.
+
+use leptos::{nonce::use_nonce, prelude::*};
+
+use crate::config::{AppConfig, SecurityConfig};
+
+/// The headers added to every response that does not set them itself.
+pub fn headers(config: &SecurityConfig) -> Vec<(&'static str, String)> {
+    let mut headers = vec![
+        ("x-content-type-options", "nosniff".to_string()),
+        ("referrer-policy", config.referrer_policy.clone()),
+    ];
+    if config.hsts_max_age_secs > 0 {
+        headers.push((
+            "strict-transport-security",
+            format!("max-age={}; includeSubDomains", config.hsts_max_age_secs),
+        ));
+    }
+    headers
+}
+
+/// Whether the value may be sent as that of a header, by the rules of
+/// both servers: no control characters other than tab.
+pub fn is_header_value(value: &str) -> bool {
+    value
+        .bytes()
+        .all(|b| b == b'\t' || (b >= b' ' && b != 0x7f))
+}
+
+/// The policy of a page whose inline scripts carry the nonce, if there
+/// is a policy.
+pub fn content_security_policy(config: &SecurityConfig, nonce: &str) -> Option<String> {
+    let policy = &config.content_security_policy;
+    (!policy.is_empty()).then(|| policy.replace("{nonce}", nonce))
+}
+
+/// Set the policy of the page being rendered to allow the nonce of its
+/// response, which the integrations add to the hydration and streaming
+/// scripts.  Must be called within the shell.
+pub fn set_content_security_policy() {
+    let (Some(config), Some(nonce)) = (use_context::<AppConfig>(), use_nonce()) else {
+        return;
+    };
+    let Some(policy) = content_security_policy(&config.security, &nonce) else {
+        return;
+    };
+    #[cfg(feature = "axum")]
+    if let (Some(response), Ok(value)) = (
+        use_context::<leptos_axum::ResponseOptions>(),
+        axum::http::HeaderValue::from_str(&policy),
+    ) {
+        response.insert_header(axum::http::header::CONTENT_SECURITY_POLICY, value);
+    }
+    #[cfg(feature = "actix")]
+    if let (Some(response), Ok(value)) = (
+        use_context::<leptos_actix::ResponseOptions>(),
+        actix_web::http::header::HeaderValue::from_str(&policy),
+    ) {
+        response.insert_header(actix_web::http::header::CONTENT_SECURITY_POLICY, value);
+    }
+    #[cfg(not(any(feature = "axum", feature = "actix")))]
+    let _ = policy;
+}
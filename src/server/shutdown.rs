@@ -0,0 +1,71 @@
+use leptos::prelude::on_cleanup;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    OnceLock,
+};
+use tokio::sync::watch;
+
+static IN_FLIGHT: AtomicUsize = AtomicUsize::new(0);
+
+fn requested_channel() -> &'static watch::Sender<bool> {
+    static REQUESTED: OnceLock<watch::Sender<bool>> = OnceLock::new();
+    REQUESTED.get_or_init(|| watch::channel(false).0)
+}
+
+/// Count the current render as in-flight until its reactive owner is
+/// cleaned up at the end of the response stream, which includes any time
+/// spent by portlets parked in `WaiterHandle::wait`.  Must be called
+/// within the app function.
+pub fn track_render() {
+    IN_FLIGHT.fetch_add(1, Ordering::SeqCst);
+    on_cleanup(|| {
+        IN_FLIGHT.fetch_sub(1, Ordering::SeqCst);
+    });
+}
+
+/// The number of SSR responses currently being rendered.
+pub fn in_flight() -> usize {
+    IN_FLIGHT.load(Ordering::SeqCst)
+}
+
+/// Resolves once shutdown is requested, for responses that would otherwise
+/// never complete, such as event streams.
+pub async fn requested() {
+    let _ = requested_channel()
+        .subscribe()
+        .wait_for(|requested| *requested)
+        .await;
+}
+
+/// Request shutdown, ending the responses waiting on `requested`.
+pub fn request() {
+    requested_channel().send_replace(true);
+    tracing::info!(
+        in_flight = in_flight(),
+        "shutdown requested, draining in-flight responses"
+    );
+}
+
+/// Resolves once SIGINT or SIGTERM is received.
+pub async fn signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("failed to install SIGINT handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
@@ -0,0 +1,112 @@
+//! The sitemap, built by expanding the param segments of the routes of
+//! the app with the authors and articles in the repository, along with
+//! the robots.txt pointing crawlers at it.
+
+use std::{collections::BTreeSet, sync::OnceLock};
+
+use super::feed::escape;
+use crate::{
+    config::AppConfig,
+    repository::{Repository, RepositoryError},
+};
+
+pub const CONTENT_TYPE: &str = "application/xml; charset=utf-8";
+pub const ROBOTS_CONTENT_TYPE: &str = "text/plain; charset=utf-8";
+
+/// The routes of no use to crawlers or in a static export.
+const EXCLUDED: &[&str] = &[
+    "/login",
+    "/logout",
+    "/author/{name}/new",
+    "/article/{id}/edit",
+];
+
+/// The paths of the routes, generated once as they never change.
+fn route_paths() -> &'static [String] {
+    static PATHS: OnceLock<Vec<String>> = OnceLock::new();
+    PATHS.get_or_init(|| {
+        super::route_list()
+            .into_iter()
+            .map(|route| route.path)
+            .filter(|path| !EXCLUDED.contains(&path.as_str()))
+            .collect()
+    })
+}
+
+/// Every path of the app with its params filled in from the repository;
+/// routes with params that cannot be enumerated are left out.
+pub fn paths(repository: &Repository) -> Result<BTreeSet<String>, RepositoryError> {
+    let names = repository
+        .list_authors()?
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>();
+    let ids = repository
+        .list_articles()?
+        .into_iter()
+        .map(|(id, _)| id.to_string())
+        .collect::<Vec<_>>();
+
+    let mut paths = BTreeSet::new();
+    for route in route_paths() {
+        let mut expanded = vec![String::new()];
+        for segment in route.split_inclusive('/') {
+            let (param, slash) = match segment.strip_suffix('/') {
+                Some(param) => (param, "/"),
+                None => (segment, ""),
+            };
+            let values = match param {
+                "{name}" => &names,
+                "{id}" => &ids,
+                _ if param.starts_with('{') || param.starts_with('*') => {
+                    tracing::debug!(route, param, "leaving route out of sitemap");
+                    expanded.clear();
+                    break;
+                }
+                _ => {
+                    expanded.iter_mut().for_each(|path| path.push_str(segment));
+                    continue;
+                }
+            };
+            expanded = expanded
+                .iter()
+                .flat_map(|path| {
+                    values
+                        .iter()
+                        .map(move |value| format!("{path}{value}{slash}"))
+                })
+                .collect();
+        }
+        paths.extend(expanded);
+    }
+    Ok(paths)
+}
+
+/// The sitemap of every page; the content has no revision timestamps, so
+/// no `lastmod` is given.
+pub fn sitemap(repository: &Repository, config: &AppConfig) -> Result<String, RepositoryError> {
+    let base = config.public_url.trim_end_matches('/');
+    let urls = paths(repository)?
+        .into_iter()
+        .map(|path| {
+            format!(
+                "  <url><loc>{}</loc></url>\n",
+                escape(&format!("{base}{path}"))
+            )
+        })
+        .collect::<String>();
+    Ok(format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+{urls}</urlset>
+"#
+    ))
+}
+
+/// Allow crawling of all pages but not of the endpoints outside the app.
+pub fn robots(config: &AppConfig) -> String {
+    let base = config.public_url.trim_end_matches('/');
+    format!(
+        "User-agent: *\nDisallow: /api/\nDisallow: /metrics\nAllow: /\n\nSitemap: {base}/sitemap.xml\n"
+    )
+}
@@ -0,0 +1,94 @@
+//! Export of every page of the app as static HTML, rendered through the
+//! same server used by `serve` so that the output hydrates as usual.
+
+use std::path::{Path, PathBuf};
+
+use super::AppState;
+use crate::{cli::CliError, static_site::MARKER};
+
+/// Render every page into `output` as `index.html` files, alongside a copy
+/// of the site root holding the `pkg/` assets, returning the number of
+/// pages written.
+pub async fn export(mut state: AppState, output: &Path) -> Result<usize, CliError> {
+    // a plain file server ignores the query string, so every listing must
+    // fit on its first page, and there is nobody waiting on the latency
+    state.config.page_size = usize::MAX;
+    state.config.latency_ms = 0;
+
+    let site_root = PathBuf::from(state.leptos_options.site_root.as_ref());
+    copy_dir(&site_root, output)?;
+
+    let pages = super::sitemap::paths(&state.repository)?
+        .into_iter()
+        .collect::<Vec<_>>();
+    let rendered = render(state, &pages).await.map_err(|source| CliError::Io {
+        path: output.to_path_buf(),
+        source,
+    })?;
+    for (page, (status, html)) in pages.iter().zip(rendered) {
+        if status != 200 {
+            return Err(CliError::Render {
+                page: page.clone(),
+                status,
+            });
+        }
+        // mark the page so the client follows links with full page loads,
+        // as there are no server functions to fetch the next page with
+        let html = html.replacen(
+            "<head>",
+            &format!(r#"<head><meta name="{MARKER}" content="true"/>"#),
+            1,
+        );
+        let path = output.join(page.trim_matches('/')).join("index.html");
+        write(&path, html)?;
+        tracing::debug!(page, path = %path.display(), "exported page");
+    }
+    Ok(pages.len())
+}
+
+/// Render each page with the server of the integration in use, returning
+/// the status and body of each.
+async fn render(state: AppState, pages: &[String]) -> std::io::Result<Vec<(u16, String)>> {
+    #[cfg(feature = "axum")]
+    {
+        super::axum::render_pages(state, pages).await
+    }
+    #[cfg(all(feature = "actix", not(feature = "axum")))]
+    {
+        super::actix::render_pages(state, pages).await
+    }
+    #[cfg(not(any(feature = "axum", feature = "actix")))]
+    {
+        let _ = (state, pages);
+        Ok(Vec::new())
+    }
+}
+
+fn write(path: &Path, contents: String) -> Result<(), CliError> {
+    let io_error = |source| CliError::Io {
+        path: path.to_path_buf(),
+        source,
+    };
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(io_error)?;
+    }
+    std::fs::write(path, contents).map_err(io_error)
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<(), CliError> {
+    let io_error = |path: &Path| {
+        let path = path.to_path_buf();
+        move |source| CliError::Io { path, source }
+    };
+    std::fs::create_dir_all(to).map_err(io_error(to))?;
+    for entry in std::fs::read_dir(from).map_err(io_error(from))? {
+        let entry = entry.map_err(io_error(from))?;
+        let target = to.join(entry.file_name());
+        if entry.file_type().map_err(io_error(&entry.path()))?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            std::fs::copy(entry.path(), &target).map_err(io_error(&target))?;
+        }
+    }
+    Ok(())
+}
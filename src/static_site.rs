@@ -0,0 +1,44 @@
+//! Support for pages exported by the `export-static` command, which are
+//! served without any server functions behind them.
+
+/// The name of the `<meta>` tag marking a page as statically exported.
+pub const MARKER: &str = "static-site";
+
+/// Whether the page was statically exported, having no server behind it.
+#[cfg(feature = "hydrate")]
+pub fn is_static() -> bool {
+    let selector = format!("meta[name=\"{MARKER}\"]");
+    matches!(
+        leptos::prelude::document().query_selector(&selector),
+        Ok(Some(_))
+    )
+}
+
+/// On statically exported pages, have the browser follow every link with a
+/// full page load rather than letting the router fetch the next page
+/// through server functions.
+#[cfg(feature = "hydrate")]
+pub fn follow_links_natively() {
+    use wasm_bindgen::{closure::Closure, JsCast};
+
+    if !is_static() {
+        return;
+    }
+    // runs in the capture phase, before the router's own click handler,
+    // which leaves links marked as external to the browser
+    let handler = Closure::<dyn Fn(web_sys::Event)>::new(|ev: web_sys::Event| {
+        let anchor = ev
+            .target()
+            .and_then(|target| target.dyn_into::<web_sys::Element>().ok())
+            .and_then(|element| element.closest("a[href]").ok().flatten());
+        if let Some(anchor) = anchor {
+            let _ = anchor.set_attribute("rel", "external");
+        }
+    });
+    let _ = leptos::prelude::window().add_event_listener_with_callback_and_bool(
+        "click",
+        handler.as_ref().unchecked_ref(),
+        true,
+    );
+    handler.forget();
+}
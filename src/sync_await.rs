@@ -3,8 +3,13 @@ use leptos::prelude::*;
 #[cfg(feature = "ssr")]
 pub mod ssr {
     use super::*;
-    use std::sync::{Arc, RwLock};
+    use crate::metrics::{self, WaiterSubscriber};
+    use std::{
+        sync::{Arc, RwLock},
+        time::{Duration, Instant},
+    };
     use tokio::sync::broadcast::{channel, Receiver, Sender};
+    use tracing::Instrument;
 
     #[derive(Clone)]
     struct Message;
@@ -23,6 +28,7 @@ pub mod ssr {
     struct WaiterHandleInner {
         waiter: Waiter,
         receiver: Receiver<Message>,
+        _subscriber: WaiterSubscriber,
     }
 
     pub struct WaiterHandle(Option<WaiterHandleInner>);
@@ -32,6 +38,7 @@ pub mod ssr {
             WaiterHandle(self.0.clone().map(|waiter| WaiterHandleInner {
                 waiter: waiter.clone(),
                 receiver: waiter.0.sender.subscribe(),
+                _subscriber: WaiterSubscriber::new(),
             }))
         }
     }
@@ -43,41 +50,61 @@ pub mod ssr {
 
         pub fn count() {
             let waiter = expect_context::<Waiter>();
-            leptos::logging::log!("count of subscribers: {}", waiter.0.sender.receiver_count());
+            tracing::debug!(
+                subscribers = waiter.0.sender.receiver_count(),
+                "waiter subscriber count",
+            );
         }
 
         pub(super) fn complete() {
             let waiter = expect_context::<Waiter>();
             *waiter.0.resolved.write().unwrap() = true;
-            if let Ok(_) = waiter.0.sender.send(Message) {
-                leptos::logging::log!(
-                    "broadcasted complete to {} subscribers",
-                    waiter.0.sender.receiver_count(),
+            metrics::record_waiter_completion(waiter.0.sender.receiver_count());
+            if waiter.0.sender.send(Message).is_ok() {
+                tracing::debug!(
+                    subscribers = waiter.0.sender.receiver_count(),
+                    "broadcasted completion",
                 );
             } else {
-                leptos::logging::log!("no subscribers available to receive completion");
+                tracing::debug!("no subscribers available to receive completion");
             }
         }
     }
 
     impl WaiterHandle {
-        pub async fn wait(mut self) {
-            if let Some(mut inner) = self.0.take() {
-                leptos::logging::log!("waiter has handle... checking resolved status");
-                if !*inner.waiter.0.resolved.read().unwrap() {
-                    leptos::logging::log!("handle's waiter not resolved, waiting...");
-                    inner
-                        .receiver
-                        .recv()
-                        .await
-                        .expect("internal error: sender not properly managed");
-                    leptos::logging::log!("handle is now resolved");
-                } else {
-                    leptos::logging::log!("handle was resolved");
+        /// Wait for the `SyncAwait` that provided the waiter to complete,
+        /// giving up once the timeout elapses.
+        pub async fn wait(mut self, timeout: Duration) {
+            let Some(mut inner) = self.0.take() else {
+                tracing::trace!("no waiter provided, not waiting");
+                return;
+            };
+            let span = tracing::debug_span!(
+                "waiter",
+                resolved = tracing::field::Empty,
+                wait_ms = tracing::field::Empty,
+            );
+            async move {
+                if *inner.waiter.0.resolved.read().unwrap() {
+                    tracing::Span::current().record("resolved", true);
+                    return;
+                }
+                tracing::Span::current().record("resolved", false);
+                let start = Instant::now();
+                let received = tokio::time::timeout(timeout, inner.receiver.recv()).await;
+                let elapsed = start.elapsed();
+                tracing::Span::current().record("wait_ms", elapsed.as_millis() as u64);
+                metrics::record_wait(elapsed);
+                match received {
+                    Ok(received) => {
+                        received.expect("internal error: sender not properly managed");
+                        tracing::debug!(?elapsed, "waiter resolved");
+                    }
+                    Err(_) => tracing::warn!(?timeout, "waiter timed out"),
                 }
-            } else {
-                leptos::logging::log!("there's no waiter!");
             }
+            .instrument(span)
+            .await
         }
     }
 
@@ -95,14 +122,14 @@ use ssr::*;
 
 #[component]
 pub fn SyncAwait(children: Children) -> impl IntoView {
-    leptos::logging::log!("entering SyncAwait");
+    tracing::trace!("entering SyncAwait");
     #[cfg(feature = "ssr")]
     provide_async_wait();
 
     let exit = move || {
         #[cfg(feature = "ssr")]
         Waiter::complete();
-        leptos::logging::log!("exiting SyncAwait");
+        tracing::trace!("exiting SyncAwait");
     };
 
     view! {
@@ -0,0 +1,28 @@
+use tracing_subscriber::{fmt, prelude::*, EnvFilter};
+
+/// The filter used when neither `RUST_LOG` nor a log level is set.
+/// Debug builds report what this crate is doing (requests, portlets,
+/// waiters) while release builds stay silent unless explicitly asked
+/// otherwise.
+fn default_filter() -> EnvFilter {
+    if cfg!(debug_assertions) {
+        EnvFilter::new("info,leptos_demo_portlet=debug")
+    } else {
+        EnvFilter::new("off")
+    }
+}
+
+/// Install the global tracing subscriber, configured via `RUST_LOG`, or
+/// failing that the configured log level, which `AppConfig::load` has
+/// made sure is valid.  Logs are written to stderr so that the output of
+/// commands such as `export` can be piped.
+pub fn init(log_level: Option<&str>) {
+    let filter = EnvFilter::try_from_default_env()
+        .ok()
+        .or_else(|| log_level.and_then(|level| EnvFilter::try_new(level).ok()))
+        .unwrap_or_else(default_filter);
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().with_writer(std::io::stderr))
+        .init();
+}
@@ -0,0 +1,108 @@
+#![cfg(any(feature = "axum", feature = "actix"))]
+
+mod common;
+
+use common::{Request, Server};
+use leptos_demo_portlet::server::api::CONTENT_TYPE;
+use serde_json::{json, Value};
+
+/// The path of each endpoint and the status and JSON body expected of it.
+fn cases() -> Vec<(&'static str, u16, Value)> {
+    let carl = json!({"id": "carl", "name": "Carl", "email": "c.smith@example.com"});
+    let summary = json!({"id": 8, "author": "carl", "title": "A quick summary on..."});
+    vec![
+        ("/api/v1/authors/carl", 200, carl),
+        (
+            "/api/v1/authors/nobody",
+            404,
+            json!({"error": "There is no author nobody."}),
+        ),
+        ("/api/v1/articles?author=carl", 200, json!([summary])),
+        (
+            "/api/v1/articles?author=nobody",
+            404,
+            json!({"error": "There is no author nobody."}),
+        ),
+        ("/api/v1/articles/8", 200, summary),
+        (
+            "/api/v1/articles/99",
+            404,
+            json!({"error": "There is no article 99."}),
+        ),
+        (
+            "/api/v1/articles/abc",
+            400,
+            json!({"error": "Invalid article id."}),
+        ),
+    ]
+}
+
+async fn get(server: &Server, path: &str) -> (u16, Value) {
+    let res = server.send(Request::get(path)).await;
+    assert_eq!(res.header("content-type"), Some(CONTENT_TYPE), "{path}");
+    (res.status, serde_json::from_slice(&res.body).unwrap())
+}
+
+async fn endpoints_serve_the_content(server: Server) {
+    for (path, status, body) in cases() {
+        assert_eq!(get(&server, path).await, (status, body), "{path}");
+    }
+
+    let (status, authors) = get(&server, "/api/v1/authors").await;
+    assert_eq!(status, 200);
+    let ids: Vec<_> = authors
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|author| author["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(ids, ["albert", "bethany", "carl", "dorothy"]);
+
+    let (status, articles) = get(&server, "/api/v1/articles").await;
+    assert_eq!(status, 200);
+    assert_eq!(articles.as_array().unwrap().len(), 10);
+}
+
+async fn openapi_documents_the_endpoints(server: Server) {
+    let (status, document) = get(&server, "/api/v1/openapi.json").await;
+    assert_eq!(status, 200);
+    assert_eq!(document["info"]["title"], "Portlet demo content API");
+    for path in [
+        "/api/v1/authors",
+        "/api/v1/authors/{name}",
+        "/api/v1/articles",
+        "/api/v1/articles/{id}",
+    ] {
+        assert!(document["paths"][path]["get"].is_object(), "{path}");
+    }
+}
+
+#[cfg(feature = "axum")]
+mod axum_server {
+    use super::*;
+
+    #[tokio::test]
+    async fn endpoints_serve_the_content() {
+        super::endpoints_serve_the_content(Server::axum(common::state(|_| {}))).await;
+    }
+
+    #[tokio::test]
+    async fn openapi_documents_the_endpoints() {
+        super::openapi_documents_the_endpoints(Server::axum(common::state(|_| {}))).await;
+    }
+}
+
+#[cfg(feature = "actix")]
+mod actix_server {
+    use super::*;
+
+    #[actix_web::test]
+    async fn endpoints_serve_the_content() {
+        super::endpoints_serve_the_content(Server::actix(common::state(|_| {})).await).await;
+    }
+
+    #[actix_web::test]
+    async fn openapi_documents_the_endpoints() {
+        super::openapi_documents_the_endpoints(Server::actix(common::state(|_| {})).await).await;
+    }
+}
@@ -0,0 +1,127 @@
+#![cfg(any(feature = "axum", feature = "actix"))]
+
+mod common;
+
+use common::{Request, Response, Server, TempDir};
+use leptos_demo_portlet::{
+    events,
+    repository::Repository,
+    server::{assets::IMMUTABLE, AppState},
+};
+use std::{fs, path::Path};
+
+const WASM: &str = "/pkg/app.0123abcd4567ef89.wasm";
+const CSS: &str = "/pkg/style.css";
+const PAGE: &str = "/author/bethany/";
+
+type Case = (
+    &'static str,
+    &'static str,
+    Option<&'static str>,
+    Option<&'static str>,
+    Option<&'static str>,
+);
+
+/// The path requested with the `Accept-Encoding`, and the `Content-Encoding`
+/// and `Cache-Control` expected of the response, along with its body for
+/// the precompressed assets, which stand in for the real compressions.
+const CASES: &[Case] = &[
+    (
+        WASM,
+        "gzip, br",
+        Some("br"),
+        Some(IMMUTABLE),
+        Some("brotli"),
+    ),
+    (
+        WASM,
+        "br;q=0, gzip",
+        Some("gzip"),
+        Some(IMMUTABLE),
+        Some("gzip"),
+    ),
+    (WASM, "identity", None, Some(IMMUTABLE), Some(CONTENT)),
+    (
+        CSS,
+        "gzip",
+        Some("gzip"),
+        Some("public, max-age=3600"),
+        None,
+    ),
+    (PAGE, "gzip", Some("gzip"), Some("no-cache"), None),
+    (events::PATH, "gzip", None, Some("no-cache"), None),
+    ("/api/v1/authors", "identity", None, None, None),
+];
+
+/// Content long enough to be worth compressing.
+const CONTENT: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// A site root holding the assets of the cases, removed when dropped.
+fn site() -> TempDir {
+    let root = TempDir::new("assets");
+    let pkg = root.path().join("pkg");
+    fs::create_dir_all(&pkg).unwrap();
+    let wasm = root.path().join(WASM.trim_start_matches('/'));
+    fs::write(&wasm, CONTENT).unwrap();
+    fs::write(wasm.with_extension("wasm.br"), "brotli").unwrap();
+    fs::write(wasm.with_extension("wasm.gz"), "gzip").unwrap();
+    fs::write(root.path().join(CSS.trim_start_matches('/')), CONTENT).unwrap();
+    root
+}
+
+fn state(site_root: &Path) -> AppState {
+    let mut options = common::options();
+    options.site_root = site_root.to_string_lossy().into();
+    AppState::new(options, common::config(|_| {}), Repository::demo())
+}
+
+/// Check the response to the case.
+fn check(case: &Case, res: &Response) {
+    let (path, _, encoding, cache_control, expected) = *case;
+    assert_eq!(
+        res.header("content-encoding").filter(|e| *e != "identity"),
+        encoding,
+        "{case:?}"
+    );
+    assert_eq!(res.header("cache-control"), cache_control, "{case:?}");
+    if path == PAGE {
+        assert!(
+            res.header("vary")
+                .is_some_and(|vary| vary.contains("cookie")),
+            "{case:?}"
+        );
+    }
+    // the event stream never ends, so its body is left unread
+    if let Some(expected) = expected.filter(|_| path != events::PATH) {
+        assert_eq!(res.body, expected.as_bytes(), "{case:?}");
+    }
+}
+
+async fn assets_are_compressed_and_cached(server: Server) {
+    for case @ (path, accept_encoding, ..) in CASES {
+        let req = Request::get(path).header("accept-encoding", *accept_encoding);
+        check(case, &server.send(req).await);
+    }
+}
+
+#[cfg(feature = "axum")]
+mod axum_server {
+    use super::*;
+
+    #[tokio::test]
+    async fn assets_are_compressed_and_cached() {
+        let root = site();
+        super::assets_are_compressed_and_cached(Server::axum(state(root.path()))).await;
+    }
+}
+
+#[cfg(feature = "actix")]
+mod actix_server {
+    use super::*;
+
+    #[actix_web::test]
+    async fn assets_are_compressed_and_cached() {
+        let root = site();
+        super::assets_are_compressed_and_cached(Server::actix(state(root.path())).await).await;
+    }
+}
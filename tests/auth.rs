@@ -0,0 +1,295 @@
+#![cfg(any(feature = "axum", feature = "actix"))]
+
+mod common;
+
+use common::{Request, Server};
+use leptos::server_fn::ServerFn;
+use leptos_demo_portlet::{
+    app::{CreateArticle, UpdateArticle},
+    auth::{self, CsrfToken, CurrentUser, Login, Logout},
+    error::AppError,
+    server::AppState,
+};
+use std::mem::discriminant;
+
+type Case = (
+    Option<&'static str>,
+    &'static str,
+    &'static str,
+    Option<AppError>,
+);
+
+/// The user logged in, if any, calling the server function with the body,
+/// to which the CSRF token of their session is added, along with the error
+/// expected in the response, if any, whose reason is not compared.  Of the
+/// demo users albert is an admin, bethany an editor, carl an author and
+/// erin a reader, while article 3 is by bethany and article 8 by carl.
+fn cases() -> Vec<Case> {
+    let forbidden = || Some(AppError::Forbidden(String::new()));
+    vec![
+        (
+            None,
+            UPDATE,
+            "id=3&title=Changed",
+            Some(AppError::Unauthorized),
+        ),
+        (
+            Some("erin"),
+            CREATE,
+            "author_name=erin&title=Mine",
+            forbidden(),
+        ),
+        (Some("carl"), UPDATE, "id=3&title=Changed", forbidden()),
+        (Some("carl"), UPDATE, "id=8&title=Changed", None),
+        (Some("carl"), CREATE, "author_name=carl&title=Mine", None),
+        (
+            Some("carl"),
+            CREATE,
+            "author_name=dorothy&title=Hers",
+            forbidden(),
+        ),
+        (Some("bethany"), UPDATE, "id=8&title=Changed", None),
+        (
+            Some("bethany"),
+            CREATE,
+            "author_name=carl&title=His",
+            forbidden(),
+        ),
+        (Some("albert"), CREATE, "author_name=carl&title=His", None),
+    ]
+}
+
+const UPDATE: &str = UpdateArticle::PATH;
+const CREATE: &str = CreateArticle::PATH;
+
+fn login_body(username: &str) -> String {
+    format!("username={username}&password=demo")
+}
+
+/// The body of the case with the token, given as the response of
+/// `csrf_token`, which is `null` without a session.
+fn with_token(body: &str, token: &str) -> String {
+    let token = token.trim_matches('"').replace("null", "");
+    format!("{body}&csrf_token={token}")
+}
+
+/// Check the response is successful or else the error expected, served
+/// with its status.
+fn check(case: &Case, status: u16, body: &str) {
+    match &case.3 {
+        None => assert_eq!(status, 200, "{case:?}: {body}"),
+        Some(expected) => {
+            assert_eq!(status, expected.status(), "{case:?}: {body}");
+            let error: AppError = serde_json::from_str(body).unwrap();
+            assert_eq!(discriminant(&error), discriminant(expected), "{case:?}");
+        }
+    }
+}
+
+async fn writes_are_authorized(server: Server) {
+    for case in &cases() {
+        let (user, path, body, _) = *case;
+        let cookie = match user {
+            Some(username) => {
+                let res = server
+                    .send(Request::post(Login::PATH, login_body(username)))
+                    .await;
+                res.session()
+            }
+            None => None,
+        };
+        let token = server
+            .send(Request::post(CsrfToken::PATH, "").cookie(cookie.as_deref()))
+            .await
+            .text();
+        let req = Request::post(path, with_token(body, &token)).cookie(cookie.as_deref());
+        let res = server.send(req).await;
+        check(case, res.status, &res.text());
+    }
+}
+
+/// The user, if any, requesting a guarded page and where they are sent
+/// instead, unless they may use it.
+const GUARDS: &[(Option<&str>, &str, Option<&str>)] = &[
+    (None, "/author/carl/new", Some("/login")),
+    (None, "/article/8/edit", Some("/login")),
+    (Some("erin"), "/author/carl/new", Some("/author/carl/")),
+    (Some("erin"), "/article/8/edit", Some("/article/8/")),
+    (Some("carl"), "/author/carl/new", None),
+    (
+        Some("carl"),
+        "/author/dorothy/new",
+        Some("/author/dorothy/"),
+    ),
+    (Some("carl"), "/article/8/edit", None),
+    (Some("carl"), "/article/3/edit", Some("/article/3/")),
+    (Some("bethany"), "/article/8/edit", None),
+    (Some("albert"), "/author/carl/new", None),
+];
+
+/// The user, if any, requesting a page, and the links to the pages adding
+/// or editing articles that its navigation offers them.
+const NAV: &[(Option<&str>, &str, &[&str])] = &[
+    (None, "/author/carl/", &[]),
+    (Some("erin"), "/author/carl/", &[]),
+    (Some("carl"), "/author/carl/", &["/author/carl/new"]),
+    (
+        Some("albert"),
+        "/author/carl/",
+        &[
+            "/author/albert/new",
+            "/author/bethany/new",
+            "/author/carl/new",
+            "/author/dorothy/new",
+        ],
+    ),
+    (None, "/article/3/", &[]),
+    (Some("erin"), "/article/3/", &[]),
+    (Some("carl"), "/article/3/", &["/article/8/edit"]),
+];
+
+/// The links of the page to the pages adding or editing articles.
+fn write_links(body: &str) -> Vec<&str> {
+    body.split("href=\"")
+        .skip(1)
+        .filter_map(|rest| rest.split('"').next())
+        .filter(|href| href.ends_with("/new") || href.ends_with("/edit"))
+        .collect()
+}
+
+async fn pages_are_guarded(server: Server, state: AppState) {
+    for case @ (user, path, redirect) in GUARDS {
+        let cookie = user.map(|username| common::login(&state, username).0);
+        let req = Request::get(path)
+            .header("accept", "text/html")
+            .cookie(cookie.as_deref());
+        let res = server.send(req).await;
+        let status = if redirect.is_some() { 302 } else { 200 };
+        assert_eq!(res.status, status, "{case:?}");
+        assert_eq!(res.header("location"), *redirect, "{case:?}");
+    }
+}
+
+async fn nav_is_filtered(server: Server, state: AppState) {
+    for case @ (user, path, links) in NAV {
+        let cookie = user.map(|username| common::login(&state, username).0);
+        let res = server
+            .send(Request::get(path).cookie(cookie.as_deref()))
+            .await;
+        assert_eq!(write_links(&res.text()), *links, "{case:?}");
+    }
+    // an editor may edit every article
+    let (cookie, _) = common::login(&state, "bethany");
+    let res = server
+        .send(Request::get("/article/3/").cookie(Some(&cookie)))
+        .await;
+    let edits: Vec<_> = (1..=10).map(|id| format!("/article/{id}/edit")).collect();
+    assert_eq!(write_links(&res.text()), edits);
+}
+
+/// The response of `current_user` for the session.
+async fn current_user(server: &Server, cookie: &str) -> String {
+    server
+        .send(Request::post(CurrentUser::PATH, "").cookie(Some(cookie)))
+        .await
+        .text()
+}
+
+async fn sessions_start_and_end(server: Server, secure: bool) {
+    let res = server
+        .send(Request::post(Login::PATH, "username=carl&password=wrong"))
+        .await;
+    assert_eq!(res.status, 400, "{}", res.text());
+    assert_eq!(res.header("set-cookie"), None);
+
+    let res = server
+        .send(Request::post(Login::PATH, login_body("carl")))
+        .await;
+    let set_cookie = res.header("set-cookie").expect("session cookie");
+    let mut attributes: Vec<_> = set_cookie.split("; ").skip(1).collect();
+    attributes.sort();
+    let mut expected = vec!["HttpOnly", "Max-Age=86400", "Path=/", "SameSite=Lax"];
+    if secure {
+        expected.push("Secure");
+    }
+    assert_eq!(attributes, expected, "{set_cookie}");
+    let cookie = res.session().unwrap();
+    assert!(
+        cookie.starts_with(&format!("{}=", auth::COOKIE)),
+        "{cookie}"
+    );
+    assert!(current_user(&server, &cookie).await.contains("\"carl\""));
+
+    let token = server
+        .send(Request::post(CsrfToken::PATH, "").cookie(Some(&cookie)))
+        .await
+        .text();
+    let res = server
+        .send(Request::post(Logout::PATH, with_token("", &token)).cookie(Some(&cookie)))
+        .await;
+    let set_cookie = res.header("set-cookie").expect("removed session cookie");
+    assert!(
+        set_cookie.starts_with(&format!("{}=;", auth::COOKIE)) && set_cookie.contains("Max-Age=0"),
+        "{set_cookie}"
+    );
+    // the session is gone from the server, not just from the browser
+    assert_eq!(current_user(&server, &cookie).await, "null");
+}
+
+#[cfg(feature = "axum")]
+mod axum_server {
+    use super::*;
+
+    #[tokio::test]
+    async fn writes_are_authorized() {
+        super::writes_are_authorized(Server::axum(common::state(|_| {}))).await;
+    }
+
+    #[tokio::test]
+    async fn pages_are_guarded() {
+        let state = common::state(|_| {});
+        super::pages_are_guarded(Server::axum(state.clone()), state).await;
+    }
+
+    #[tokio::test]
+    async fn nav_is_filtered() {
+        let state = common::state(|_| {});
+        super::nav_is_filtered(Server::axum(state.clone()), state).await;
+    }
+
+    #[tokio::test]
+    async fn sessions_start_and_end() {
+        super::sessions_start_and_end(Server::axum(common::state(|_| {})), false).await;
+        let state = common::state(|config| config.public_url = "https://example.com/".into());
+        super::sessions_start_and_end(Server::axum(state), true).await;
+    }
+}
+
+#[cfg(feature = "actix")]
+mod actix_server {
+    use super::*;
+
+    #[actix_web::test]
+    async fn writes_are_authorized() {
+        super::writes_are_authorized(Server::actix(common::state(|_| {})).await).await;
+    }
+
+    #[actix_web::test]
+    async fn pages_are_guarded() {
+        let state = common::state(|_| {});
+        super::pages_are_guarded(Server::actix(state.clone()).await, state).await;
+    }
+
+    #[actix_web::test]
+    async fn nav_is_filtered() {
+        let state = common::state(|_| {});
+        super::nav_is_filtered(Server::actix(state.clone()).await, state).await;
+    }
+
+    #[actix_web::test]
+    async fn sessions_start_and_end() {
+        super::sessions_start_and_end(Server::actix(common::state(|_| {})).await, false).await;
+        let state = common::state(|config| config.public_url = "https://example.com/".into());
+        super::sessions_start_and_end(Server::actix(state).await, true).await;
+    }
+}
@@ -0,0 +1,115 @@
+#![cfg(feature = "ssr")]
+
+use leptos_demo_portlet::cache::{Cache, MAX_ENTRIES};
+use std::{cell::Cell, convert::Infallible, time::Duration};
+
+const TTL: Duration = Duration::from_secs(60);
+
+/// Look the function up for the arguments, counting the fetches made.
+async fn lookup(cache: &Cache, function: &'static str, args: u32, fetches: &Cell<u32>) -> u32 {
+    cache
+        .get_or_fetch(function, &args, || async {
+            fetches.set(fetches.get() + 1);
+            Ok::<_, Infallible>(args)
+        })
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn results_are_cached_by_function_and_arguments() {
+    let cache = Cache::new(TTL);
+    let fetches = Cell::new(0);
+    assert_eq!(lookup(&cache, "get_article", 1, &fetches).await, 1);
+    assert_eq!(lookup(&cache, "get_article", 1, &fetches).await, 1);
+    assert_eq!(fetches.get(), 1);
+    assert_eq!(lookup(&cache, "get_article", 2, &fetches).await, 2);
+    assert_eq!(lookup(&cache, "get_author", 1, &fetches).await, 1);
+    assert_eq!(fetches.get(), 3);
+}
+
+#[tokio::test]
+async fn errors_are_not_cached() {
+    let cache = Cache::new(TTL);
+    let fetches = Cell::new(0);
+    for _ in 0..2 {
+        let result = cache
+            .get_or_fetch("get_article", &1, || async {
+                fetches.set(fetches.get() + 1);
+                Err::<u32, _>("missing")
+            })
+            .await;
+        assert_eq!(result, Err("missing"));
+    }
+    assert_eq!(fetches.get(), 2);
+}
+
+#[tokio::test]
+async fn entries_expire() {
+    let cache = Cache::new(Duration::from_millis(20));
+    let fetches = Cell::new(0);
+    lookup(&cache, "get_article", 1, &fetches).await;
+    tokio::time::sleep(Duration::from_millis(40)).await;
+    lookup(&cache, "get_article", 1, &fetches).await;
+    assert_eq!(fetches.get(), 2);
+}
+
+#[tokio::test]
+async fn a_zero_ttl_disables_caching() {
+    let cache = Cache::new(Duration::ZERO);
+    let fetches = Cell::new(0);
+    lookup(&cache, "get_article", 1, &fetches).await;
+    lookup(&cache, "get_article", 1, &fetches).await;
+    assert_eq!(fetches.get(), 2);
+}
+
+#[tokio::test]
+async fn invalidation_drops_the_functions() {
+    let cache = Cache::new(TTL);
+    let fetches = Cell::new(0);
+    lookup(&cache, "get_article", 1, &fetches).await;
+    lookup(&cache, "get_article", 2, &fetches).await;
+    lookup(&cache, "get_author", 1, &fetches).await;
+    cache.invalidate(&["get_article"]);
+    lookup(&cache, "get_article", 1, &fetches).await;
+    lookup(&cache, "get_article", 2, &fetches).await;
+    lookup(&cache, "get_author", 1, &fetches).await;
+    assert_eq!(fetches.get(), 5);
+}
+
+#[tokio::test]
+async fn a_fetch_during_invalidation_is_not_cached() {
+    let cache = Cache::new(TTL);
+    let fetches = Cell::new(0);
+    let stale = cache
+        .get_or_fetch("get_article", &1, || async {
+            // a write completing while the read is in flight
+            cache.invalidate(&["get_article"]);
+            Ok::<_, Infallible>("stale")
+        })
+        .await;
+    assert_eq!(stale, Ok("stale"));
+    let fresh = cache
+        .get_or_fetch("get_article", &1, || async {
+            fetches.set(fetches.get() + 1);
+            Ok::<_, Infallible>("fresh")
+        })
+        .await;
+    assert_eq!(fresh, Ok("fresh"));
+    assert_eq!(fetches.get(), 1);
+}
+
+#[tokio::test]
+async fn entries_are_capped() {
+    let cache = Cache::new(TTL);
+    let fetches = Cell::new(0);
+    for args in 0..=MAX_ENTRIES as u32 {
+        lookup(&cache, "get_article", args, &fetches).await;
+    }
+    let fetched = fetches.get();
+    // the entry expiring first made room for the last one
+    lookup(&cache, "get_article", MAX_ENTRIES as u32, &fetches).await;
+    assert_eq!(fetches.get(), fetched);
+    lookup(&cache, "get_article", 0, &fetches).await;
+    assert_eq!(fetches.get(), fetched + 1);
+}
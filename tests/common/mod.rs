@@ -0,0 +1,264 @@
+//! The setup shared by the tests, which run each case against both
+//! servers through the same `Server`, so that a test is written once and
+//! only started per server by its `axum_server` and `actix_server` modules.
+
+#![allow(dead_code)]
+
+use futures::future::LocalBoxFuture;
+use leptos::prelude::LeptosOptions;
+use leptos_demo_portlet::{auth, config::AppConfig, repository::Repository, server::AppState};
+use std::{
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+pub const FORM: &str = "application/x-www-form-urlencoded";
+
+pub fn options() -> LeptosOptions {
+    LeptosOptions::builder()
+        .output_name("leptos_demo_portlet")
+        .build()
+}
+
+/// The configuration without simulated latency, with the overrides of
+/// the test applied.
+pub fn config(overrides: impl FnOnce(&mut AppConfig)) -> AppConfig {
+    let mut config = AppConfig {
+        latency_ms: 0,
+        ..AppConfig::default()
+    };
+    overrides(&mut config);
+    config
+}
+
+/// The state serving the demo content with the overrides of the test
+/// applied to the configuration.
+pub fn state(overrides: impl FnOnce(&mut AppConfig)) -> AppState {
+    AppState::new(options(), config(overrides), Repository::demo())
+}
+
+/// Log the user in, returning the `Cookie` of the session and its CSRF
+/// token.
+pub fn login(state: &AppState, username: &str) -> (String, String) {
+    let id = state.sessions.create(username);
+    let token = state.sessions.csrf_token(&id).unwrap();
+    (format!("{}={id}", auth::COOKIE), token)
+}
+
+/// A request made to either server.
+#[derive(Clone, Debug)]
+pub struct Request {
+    pub method: &'static str,
+    pub path: String,
+    pub headers: Vec<(&'static str, String)>,
+    pub body: String,
+    /// The address of the client, if the server is to know it.
+    pub peer: Option<SocketAddr>,
+}
+
+impl Request {
+    pub fn get(path: &str) -> Self {
+        Self {
+            method: "GET",
+            path: path.to_string(),
+            headers: Vec::new(),
+            body: String::new(),
+            peer: None,
+        }
+    }
+
+    /// A form posted to the path, as the server functions are called.
+    pub fn post(path: &str, body: impl Into<String>) -> Self {
+        Self {
+            method: "POST",
+            body: body.into(),
+            ..Self::get(path)
+        }
+        .header("content-type", FORM)
+    }
+
+    pub fn header(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        self.headers.push((name, value.into()));
+        self
+    }
+
+    /// The request with the `Cookie`, if any.
+    pub fn cookie(self, cookie: Option<&str>) -> Self {
+        match cookie {
+            Some(cookie) => self.header("cookie", cookie),
+            None => self,
+        }
+    }
+
+    pub fn peer(mut self, peer: SocketAddr) -> Self {
+        self.peer = Some(peer);
+        self
+    }
+}
+
+/// The response of either server.  The body of an event stream, which
+/// never ends, is left unread.
+#[derive(Debug)]
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    /// The first value of the header.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    /// The `Cookie` of the session set by the response, if any.
+    pub fn session(&self) -> Option<String> {
+        let set_cookie = self.header("set-cookie")?;
+        Some(set_cookie.split(';').next()?.to_string())
+    }
+}
+
+fn is_event_stream(content_type: Option<&str>) -> bool {
+    content_type.is_some_and(|content_type| content_type.starts_with("text/event-stream"))
+}
+
+/// Either server, taking the requests of a test.
+pub struct Server(Box<dyn Fn(Request) -> LocalBoxFuture<'static, Response>>);
+
+impl Server {
+    pub async fn send(&self, request: Request) -> Response {
+        (self.0)(request).await
+    }
+
+    #[cfg(feature = "axum")]
+    pub fn axum(state: AppState) -> Self {
+        use axum::{
+            body::{to_bytes, Body},
+            extract::ConnectInfo,
+        };
+        use tower::ServiceExt;
+
+        let app = leptos_demo_portlet::server::axum::router(state);
+        Self(Box::new(move |request| {
+            let app = app.clone();
+            Box::pin(async move {
+                let mut req = axum::http::Request::builder()
+                    .method(request.method)
+                    .uri(&request.path);
+                for (name, value) in &request.headers {
+                    req = req.header(*name, value);
+                }
+                if let Some(peer) = request.peer {
+                    req = req.extension(ConnectInfo(peer));
+                }
+                let res = app
+                    .oneshot(req.body(Body::from(request.body)).unwrap())
+                    .await
+                    .unwrap();
+                let headers = res
+                    .headers()
+                    .iter()
+                    .map(|(name, value)| {
+                        (name.to_string(), value.to_str().unwrap_or("").to_string())
+                    })
+                    .collect::<Vec<_>>();
+                let status = res.status().as_u16();
+                let body = if is_event_stream(
+                    res.headers()
+                        .get("content-type")
+                        .and_then(|value| value.to_str().ok()),
+                ) {
+                    Vec::new()
+                } else {
+                    to_bytes(res.into_body(), usize::MAX)
+                        .await
+                        .unwrap()
+                        .to_vec()
+                };
+                Response {
+                    status,
+                    headers,
+                    body,
+                }
+            })
+        }))
+    }
+
+    #[cfg(feature = "actix")]
+    pub async fn actix(state: AppState) -> Self {
+        use actix_web::test::{call_service, init_service, read_body, TestRequest};
+        use std::rc::Rc;
+
+        let app = Rc::new(init_service(leptos_demo_portlet::server::actix::app(state)).await);
+        Self(Box::new(move |request| {
+            let app = app.clone();
+            Box::pin(async move {
+                let method = request.method.parse().unwrap();
+                let mut req = TestRequest::default().method(method).uri(&request.path);
+                for (name, value) in request.headers {
+                    req = req.insert_header((name, value));
+                }
+                if let Some(peer) = request.peer {
+                    req = req.peer_addr(peer);
+                }
+                let res = call_service(&*app, req.set_payload(request.body).to_request()).await;
+                let headers = res
+                    .headers()
+                    .iter()
+                    .map(|(name, value)| {
+                        (name.to_string(), value.to_str().unwrap_or("").to_string())
+                    })
+                    .collect::<Vec<_>>();
+                let status = res.status().as_u16();
+                let body = if is_event_stream(
+                    res.headers()
+                        .get("content-type")
+                        .and_then(|value| value.to_str().ok()),
+                ) {
+                    Vec::new()
+                } else {
+                    read_body(res).await.to_vec()
+                };
+                Response {
+                    status,
+                    headers,
+                    body,
+                }
+            })
+        }))
+    }
+}
+
+/// A directory removed once the test is done with it, even when it fails.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "portlet-{name}-{}-{}",
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,49 @@
+#![cfg(feature = "ssr")]
+
+mod common;
+
+use leptos_demo_portlet::config::{AppConfig, ConfigArgs};
+
+#[test]
+fn invalid_log_level_is_rejected() {
+    let args = |level: &str| ConfigArgs {
+        log_level: Some(level.to_string()),
+        ..ConfigArgs::default()
+    };
+    let config = AppConfig::load(&args("warn,leptos_demo_portlet=debug")).unwrap();
+    assert_eq!(
+        config.log_level.as_deref(),
+        Some("warn,leptos_demo_portlet=debug")
+    );
+    let err = AppConfig::load(&args("leptos_demo_portlet=loud")).unwrap_err();
+    assert!(err.to_string().contains("invalid log level"), "{err}");
+}
+
+#[test]
+fn invalid_header_value_is_rejected() {
+    let dir = common::TempDir::new("config");
+    for key in ["content_security_policy", "referrer_policy"] {
+        let path = dir.path().join(format!("{key}.toml"));
+        std::fs::write(&path, format!("[security]\n{key} = \"no-referrer\\n\"\n")).unwrap();
+        let args = ConfigArgs {
+            config: Some(path),
+            ..ConfigArgs::default()
+        };
+        let err = AppConfig::load(&args).unwrap_err();
+        assert!(err.to_string().contains("invalid header value"), "{err}");
+        assert_eq!(err.path, ["security", key]);
+    }
+}
+
+#[test]
+fn grace_period_is_set_on_the_command_line() {
+    let args = ConfigArgs {
+        shutdown_grace_period_secs: Some(5),
+        ..ConfigArgs::default()
+    };
+    let config = AppConfig::load(&args).unwrap();
+    assert_eq!(
+        config.shutdown_grace_period(),
+        std::time::Duration::from_secs(5)
+    );
+}
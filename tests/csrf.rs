@@ -0,0 +1,100 @@
+#![cfg(any(feature = "axum", feature = "actix"))]
+
+mod common;
+
+use common::{Request, Server};
+use leptos::server_fn::ServerFn;
+use leptos_demo_portlet::{
+    app::UpdateArticle,
+    auth::{self, Login},
+    server::AppState,
+};
+
+/// Where the site is served from.
+const SITE: &str = "http://127.0.0.1:4000";
+
+/// The `Origin` and `Referer` of a request changing article 3 as albert,
+/// whether it carries the CSRF token of the session, and the status
+/// expected of it.
+const CASES: &[(Option<&str>, Option<&str>, bool, u16)] = &[
+    (Some(SITE), None, true, 200),
+    (
+        None,
+        Some("http://127.0.0.1:4000/article/3/edit"),
+        true,
+        200,
+    ),
+    // clients other than browsers send neither
+    (None, None, true, 200),
+    (Some("https://evil.example"), None, true, 403),
+    (None, Some("https://evil.example/page"), true, 403),
+    (Some("null"), None, true, 403),
+    (Some(SITE), None, false, 403),
+    (None, None, false, 403),
+];
+
+/// A login from another site, rejected although there is no session.
+const LOGIN: &str = "username=albert&password=demo";
+
+fn state() -> AppState {
+    common::state(|config| config.public_url = SITE.to_string())
+}
+
+fn body(token: Option<&str>) -> String {
+    let token = token.unwrap_or("wrong");
+    format!("id=3&title=Changed&{}={token}", auth::CSRF_FIELD)
+}
+
+/// Run the cases as albert, logged in on the server with the state.
+async fn cross_site_requests_are_rejected(server: Server, cookie: String, token: String) {
+    for case @ (origin, referer, with_token, status) in CASES {
+        let mut req = Request::post(UpdateArticle::PATH, body(with_token.then_some(&token)))
+            .cookie(Some(&cookie));
+        if let Some(origin) = origin {
+            req = req.header("origin", *origin);
+        }
+        if let Some(referer) = referer {
+            req = req.header("referer", *referer);
+        }
+        assert_eq!(server.send(req).await.status, *status, "{case:?}");
+    }
+
+    let req = Request::post(Login::PATH, LOGIN).header("origin", "https://evil.example");
+    assert_eq!(server.send(req).await.status, 403);
+
+    // hydrated forms send the token in a header, which must be right
+    // whatever the form carries
+    for (header, form, status) in [
+        (token.as_str(), None, 200),
+        ("wrong", Some(token.as_str()), 403),
+    ] {
+        let req = Request::post(UpdateArticle::PATH, body(form))
+            .cookie(Some(&cookie))
+            .header(auth::CSRF_HEADER, header);
+        assert_eq!(server.send(req).await.status, status, "{header}");
+    }
+}
+
+#[cfg(feature = "axum")]
+mod axum_server {
+    use super::*;
+
+    #[tokio::test]
+    async fn cross_site_requests_are_rejected() {
+        let state = state();
+        let (cookie, token) = common::login(&state, "albert");
+        super::cross_site_requests_are_rejected(Server::axum(state), cookie, token).await;
+    }
+}
+
+#[cfg(feature = "actix")]
+mod actix_server {
+    use super::*;
+
+    #[actix_web::test]
+    async fn cross_site_requests_are_rejected() {
+        let state = state();
+        let (cookie, token) = common::login(&state, "albert");
+        super::cross_site_requests_are_rejected(Server::actix(state).await, cookie, token).await;
+    }
+}
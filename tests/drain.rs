@@ -0,0 +1,189 @@
+#![cfg(any(feature = "axum", feature = "actix"))]
+
+//! Shutdown is requested of the whole process, so these tests have a
+//! binary of their own.
+
+mod common;
+
+use std::{
+    future::Future,
+    io::{Read, Write},
+    net::{SocketAddr, TcpStream},
+    time::{Duration, Instant},
+};
+
+/// The page rendered during shutdown, whose portlets wait on the route.
+const PAGE: &str = "/author/carl/";
+
+/// How long the render of the page is under way before shutdown.
+const STARTED: Duration = Duration::from_millis(300);
+
+/// How much longer than the grace period the server may take to stop.
+const MARGIN: Duration = Duration::from_secs(2);
+
+/// The whole response to a GET of `path`, read until the server closes
+/// the connection, complete or not.
+fn get(addr: SocketAddr, path: &str) -> String {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(30)))
+        .unwrap();
+    write!(
+        stream,
+        "GET {path} HTTP/1.1\r\nhost: {addr}\r\nconnection: close\r\n\r\n"
+    )
+    .unwrap();
+    let mut read = Vec::new();
+    // a connection reset by an abandoned response ends it as well
+    let _ = stream.read_to_end(&mut read);
+    String::from_utf8_lossy(&read).into_owned()
+}
+
+/// Start rendering the page, stop the server with `stop` once the render
+/// is under way, and wait for `stopped`, returning the response and the
+/// time the server took to stop.
+async fn render_during_shutdown(
+    addr: SocketAddr,
+    stop: impl FnOnce(),
+    stopped: impl Future<Output = ()>,
+) -> (String, Duration) {
+    let response = tokio::task::spawn_blocking(move || get(addr, PAGE));
+    tokio::time::sleep(STARTED).await;
+    let start = Instant::now();
+    stop();
+    tokio::time::timeout(Duration::from_secs(30), stopped)
+        .await
+        .expect("the server did not stop");
+    let elapsed = start.elapsed();
+    (response.await.unwrap(), elapsed)
+}
+
+/// The latency simulated per server function, and so per wait of the
+/// page, for it to complete within the grace period.
+const DRAINED_LATENCY_MS: u64 = 500;
+
+/// Check the page, blocked on its waiter when shutdown was requested,
+/// completed within `grace_period`.
+fn assert_drained((response, elapsed): (String, Duration), grace_period: Duration) {
+    assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+    assert!(response.contains("</html>"), "{response}");
+    assert!(elapsed < grace_period, "stopped after {elapsed:?}");
+}
+
+/// The latency simulated per server function, for the page to outlast
+/// the grace period.
+const ABANDONED_LATENCY_MS: u64 = 10_000;
+
+/// Check the page was abandoned once `grace_period` elapsed.
+fn assert_abandoned((response, elapsed): (String, Duration), grace_period: Duration) {
+    assert!(!response.contains("</html>"), "{response}");
+    assert!(elapsed < grace_period + MARGIN, "stopped after {elapsed:?}");
+}
+
+#[cfg(feature = "axum")]
+mod axum_server {
+    use super::*;
+    use leptos_demo_portlet::server;
+    use std::thread;
+    use tokio::sync::oneshot;
+
+    /// Serve on a runtime of its own, which is dropped once the server
+    /// stops, as on exit, ending the connections it abandoned; returns
+    /// the address, the sender stopping the server and a future resolving
+    /// once the runtime is gone.
+    fn start(
+        latency_ms: u64,
+        grace_period: Duration,
+    ) -> (SocketAddr, oneshot::Sender<()>, impl Future<Output = ()>) {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (tx, rx) = oneshot::channel();
+        let served = thread::spawn(move || {
+            let runtime = tokio::runtime::Runtime::new().unwrap();
+            runtime
+                .block_on(async move {
+                    server::axum::serve(
+                        tokio::net::TcpListener::from_std(listener)?,
+                        server::axum::router(common::state(|config| {
+                            config.latency_ms = latency_ms
+                        })),
+                        grace_period,
+                        async move {
+                            let _ = rx.await;
+                        },
+                    )
+                    .await
+                })
+                .unwrap();
+        });
+        let stopped = async move {
+            tokio::task::spawn_blocking(move || served.join().unwrap())
+                .await
+                .unwrap()
+        };
+        (addr, tx, stopped)
+    }
+
+    #[tokio::test]
+    async fn render_completes_within_grace_period() {
+        let grace_period = Duration::from_secs(5);
+        let (addr, tx, stopped) = start(DRAINED_LATENCY_MS, grace_period);
+        let rendered = render_during_shutdown(addr, move || tx.send(()).unwrap(), stopped).await;
+        assert_drained(rendered, grace_period);
+    }
+
+    #[tokio::test]
+    async fn render_over_grace_period_is_abandoned() {
+        let grace_period = Duration::from_secs(1);
+        let (addr, tx, stopped) = start(ABANDONED_LATENCY_MS, grace_period);
+        let rendered = render_during_shutdown(addr, move || tx.send(()).unwrap(), stopped).await;
+        assert_abandoned(rendered, grace_period);
+    }
+}
+
+#[cfg(feature = "actix")]
+mod actix_server {
+    use super::*;
+    use actix_web::HttpServer;
+    use leptos_demo_portlet::server;
+    use tokio::sync::oneshot;
+
+    /// Serve on workers stopped once their responses complete or the
+    /// grace period elapses; returns the address, the sender stopping the
+    /// server and a future resolving once it has stopped.
+    fn start(
+        latency_ms: u64,
+        grace_period: Duration,
+    ) -> (SocketAddr, oneshot::Sender<()>, impl Future<Output = ()>) {
+        let state = common::state(|config| config.latency_ms = latency_ms);
+        let server = HttpServer::new(move || server::actix::app(state.clone()))
+            .workers(1)
+            .disable_signals()
+            .shutdown_timeout(grace_period.as_secs())
+            .bind("127.0.0.1:0")
+            .unwrap();
+        let addr = server.addrs()[0];
+        let (tx, rx) = oneshot::channel();
+        let served = actix_web::rt::spawn(server::actix::serve(server.run(), async move {
+            let _ = rx.await;
+        }));
+        (addr, tx, async move { served.await.unwrap().unwrap() })
+    }
+
+    #[actix_web::test]
+    async fn render_completes_within_grace_period() {
+        let grace_period = Duration::from_secs(5);
+        let (addr, tx, stopped) = start(DRAINED_LATENCY_MS, grace_period);
+        let rendered = render_during_shutdown(addr, move || tx.send(()).unwrap(), stopped).await;
+        assert_drained(rendered, grace_period);
+    }
+
+    #[actix_web::test]
+    async fn render_over_grace_period_is_abandoned() {
+        let grace_period = Duration::from_secs(1);
+        let (addr, tx, stopped) = start(ABANDONED_LATENCY_MS, grace_period);
+        let rendered = render_during_shutdown(addr, move || tx.send(()).unwrap(), stopped).await;
+        assert_abandoned(rendered, grace_period);
+    }
+}
@@ -0,0 +1,100 @@
+#![cfg(any(feature = "axum", feature = "actix"))]
+
+mod common;
+
+use any_spawner::Executor;
+use common::{Request, Server};
+use futures::StreamExt;
+use leptos::prelude::*;
+use leptos_demo_portlet::{
+    app::create_article,
+    portlet::{render_portlet, PortletCtx},
+};
+
+/// Each page that fails to load, with the status and message expected of
+/// the error page rendered in its place.
+const CASES: &[(&str, u16, &str)] = &[
+    ("/author/nobody/", 404, "There is no author nobody."),
+    ("/article/99/", 404, "There is no article 99."),
+    ("/article/abc/", 400, "Invalid article id."),
+    ("/no/such/path", 404, "There is no such page."),
+];
+
+async fn failures_have_their_status(server: Server) {
+    for (path, status, message) in CASES {
+        let res = server.send(Request::get(path)).await;
+        assert_eq!(res.status, *status, "{path}");
+        let body = res.text();
+        assert!(body.contains(message), "{path}: {body}");
+    }
+}
+
+/// Render a page whose portlet calls a server function the user is not
+/// authorized to, with the `ResponseOptions` of the server provided by
+/// `provide`; the error is the portlet's to show rather than the status
+/// of the page.
+async fn render_unauthorized_portlet(provide: impl FnOnce()) {
+    let _ = Executor::init_tokio();
+    let owner = Owner::new_root(None);
+    owner.set();
+    common::state(|_| {}).provide_contexts();
+    provide();
+    PortletCtx::<String>::provide();
+    expect_context::<WriteSignal<PortletCtx<String>>>().update(|ctx| {
+        ctx.set(ArcResource::new(
+            || (),
+            |_| async {
+                create_article("carl".to_string(), "A title".to_string())
+                    .await
+                    .map(|id| id.to_string())
+            },
+        ))
+    });
+    let html = render_portlet::<String>()
+        .to_html_stream_in_order()
+        .collect::<String>()
+        .await;
+    assert!(html.contains("portlet-error unauthorized"), "{html}");
+}
+
+#[cfg(feature = "axum")]
+mod axum_server {
+    use super::*;
+
+    #[tokio::test]
+    async fn failures_have_their_status() {
+        super::failures_have_their_status(Server::axum(common::state(|_| {}))).await;
+    }
+
+    #[tokio::test]
+    async fn portlet_errors_leave_the_status_of_the_page() {
+        let response = leptos_axum::ResponseOptions::default();
+        render_unauthorized_portlet({
+            let response = response.clone();
+            move || provide_context(response)
+        })
+        .await;
+        assert_eq!(response.0.read().unwrap().status, None);
+    }
+}
+
+#[cfg(feature = "actix")]
+mod actix_server {
+    use super::*;
+
+    #[actix_web::test]
+    async fn failures_have_their_status() {
+        super::failures_have_their_status(Server::actix(common::state(|_| {})).await).await;
+    }
+
+    #[tokio::test]
+    async fn portlet_errors_leave_the_status_of_the_page() {
+        let response = leptos_actix::ResponseOptions::default();
+        render_unauthorized_portlet({
+            let response = response.clone();
+            move || provide_context(response)
+        })
+        .await;
+        assert_eq!(response.0.read().unwrap().status, None);
+    }
+}
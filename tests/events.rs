@@ -0,0 +1,116 @@
+#![cfg(any(feature = "axum", feature = "actix"))]
+
+mod common;
+
+use common::FORM;
+use leptos::server_fn::ServerFn;
+use leptos_demo_portlet::{app::UpdateArticle, auth, events, server::AppState};
+use std::time::Duration;
+
+/// The frame expected on the event stream once the title of article 3 is
+/// changed through the server function, by a user allowed to edit it.
+const BODY: &str = "id=3&title=Changed";
+const FRAME: &str = "event: article\ndata: 3\n\n";
+
+/// How long to wait for the change to arrive on the stream.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The state with albert logged in, along with the `Cookie` of the session
+/// and the body of the change carrying its CSRF token.
+fn state() -> (AppState, String, String) {
+    let state = common::state(|_| {});
+    let (cookie, token) = common::login(&state, "albert");
+    (
+        state,
+        cookie,
+        format!("{BODY}&{}={token}", auth::CSRF_FIELD),
+    )
+}
+
+#[cfg(feature = "axum")]
+mod axum_server {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::{header, Request};
+    use futures::StreamExt;
+    use leptos_demo_portlet::server;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn article_change_is_pushed() {
+        let (state, cookie, body) = state();
+        let app = server::axum::router(state);
+        let res = app
+            .clone()
+            .oneshot(Request::get(events::PATH).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(res.headers()[header::CONTENT_TYPE], events::CONTENT_TYPE);
+        let mut stream = res.into_body().into_data_stream();
+
+        let res = app
+            .oneshot(
+                Request::post(UpdateArticle::PATH)
+                    .header(header::CONTENT_TYPE, FORM)
+                    .header(header::COOKIE, cookie)
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(res.status().as_u16(), 200);
+
+        let frame = tokio::time::timeout(TIMEOUT, stream.next())
+            .await
+            .expect("no change pushed")
+            .unwrap()
+            .unwrap();
+        assert_eq!(frame, FRAME.as_bytes());
+    }
+}
+
+#[cfg(feature = "actix")]
+mod actix_server {
+    use super::*;
+    use actix_web::{
+        body::MessageBody,
+        http::header,
+        test::{call_service, init_service, TestRequest},
+    };
+    use leptos_demo_portlet::server;
+
+    #[actix_web::test]
+    async fn article_change_is_pushed() {
+        let (state, cookie, body) = state();
+        let app = init_service(server::actix::app(state)).await;
+        let res = call_service(&app, TestRequest::get().uri(events::PATH).to_request()).await;
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            events::CONTENT_TYPE
+        );
+        let mut stream = std::pin::pin!(res.into_body());
+
+        let res = call_service(
+            &app,
+            TestRequest::post()
+                .uri(UpdateArticle::PATH)
+                .insert_header((header::CONTENT_TYPE, FORM))
+                .insert_header((header::COOKIE, cookie))
+                .set_payload(body)
+                .to_request(),
+        )
+        .await;
+        assert_eq!(res.status().as_u16(), 200);
+
+        let frame = tokio::time::timeout(
+            TIMEOUT,
+            std::future::poll_fn(|cx| stream.as_mut().poll_next(cx)),
+        )
+        .await
+        .expect("no change pushed");
+        let Some(Ok(frame)) = frame else {
+            panic!("event stream ended");
+        };
+        assert_eq!(frame, FRAME.as_bytes());
+    }
+}
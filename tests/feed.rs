@@ -0,0 +1,102 @@
+#![cfg(any(feature = "axum", feature = "actix"))]
+
+mod common;
+
+use common::{Request, Server};
+use leptos_demo_portlet::{
+    app::Article,
+    server::{feed, AppState},
+};
+
+const BASE: &str = "https://example.com";
+
+/// The path of each feed, the status and channel title expected of it and
+/// the ids of its items, newest first and no more than a page of them.
+const CASES: &[(&str, u16, &str, &[u32])] = &[
+    ("/feed.xml", 200, "All articles", &[11, 10, 9]),
+    (
+        "/author/dorothy/feed.xml",
+        200,
+        "Articles by Dorothy",
+        &[9, 7, 4],
+    ),
+    ("/author/carl/feed.xml", 200, "Articles by Carl", &[8]),
+    ("/author/nobody/feed.xml", 404, "", &[]),
+];
+
+/// The demo content with a page size of three and an eleventh article,
+/// whose title must be escaped.
+fn state() -> AppState {
+    let state = common::state(|config| {
+        config.page_size = 3;
+        config.public_url = format!("{BASE}/");
+    });
+    state
+        .repository
+        .create_article(Article {
+            author_name: "bethany".to_string(),
+            title: "Q&A <live>".to_string(),
+        })
+        .unwrap();
+    state
+}
+
+async fn feeds_list_the_latest_articles(server: Server) {
+    for case @ (path, status, title, ids) in CASES {
+        let res = server.send(Request::get(path)).await;
+        assert_eq!(res.status, *status, "{case:?}");
+        if res.status != 200 {
+            continue;
+        }
+        assert_eq!(res.header("content-type"), Some(feed::CONTENT_TYPE));
+        let body = res.text();
+        assert!(
+            body.starts_with(r#"<?xml version="1.0" encoding="utf-8"?>"#),
+            "{body}"
+        );
+        assert!(
+            body.contains(&format!("<channel>\n    <title>{title}</title>")),
+            "{body}"
+        );
+        assert!(
+            body.contains(&format!(r#"<atom:link href="{BASE}{path}" rel="self""#)),
+            "{body}"
+        );
+        let guids = body
+            .split("<guid>")
+            .skip(1)
+            .map(|item| item.split("</guid>").next().unwrap().to_string())
+            .collect::<Vec<_>>();
+        let expected = ids
+            .iter()
+            .map(|id| format!("{BASE}/article/{id}/"))
+            .collect::<Vec<_>>();
+        assert_eq!(guids, expected, "{case:?}");
+    }
+
+    let body = server.send(Request::get("/feed.xml")).await.text();
+    assert!(
+        body.contains("<title>Q&amp;A &lt;live&gt;</title>"),
+        "{body}"
+    );
+}
+
+#[cfg(feature = "axum")]
+mod axum_server {
+    use super::*;
+
+    #[tokio::test]
+    async fn feeds_list_the_latest_articles() {
+        super::feeds_list_the_latest_articles(Server::axum(state())).await;
+    }
+}
+
+#[cfg(feature = "actix")]
+mod actix_server {
+    use super::*;
+
+    #[actix_web::test]
+    async fn feeds_list_the_latest_articles() {
+        super::feeds_list_the_latest_articles(Server::actix(state()).await).await;
+    }
+}
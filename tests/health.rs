@@ -0,0 +1,77 @@
+#![cfg(any(feature = "axum", feature = "actix"))]
+
+mod common;
+
+use common::{Request, Server, TempDir};
+use leptos_demo_portlet::{
+    config::{StorageBackend, StorageConfig},
+    repository::{Content, Repository},
+    server::AppState,
+};
+
+/// Expected status and body prefix for each endpoint, with the repository
+/// either available or not.
+const CASES: &[(bool, &str, u16, &str)] = &[
+    (true, "/healthz", 200, "ok"),
+    (true, "/readyz", 200, "ok"),
+    (false, "/healthz", 200, "ok"),
+    (false, "/readyz", 503, "unavailable: "),
+];
+
+/// The state with the repository made unavailable, by removing the
+/// directory of its content file after it has been opened.
+fn unavailable() -> AppState {
+    let dir = TempDir::new("health");
+    let path = dir.path().join("content.json");
+    Content::demo().save(&path).unwrap();
+    let repository = Repository::open(&StorageConfig {
+        backend: StorageBackend::Json,
+        path,
+    })
+    .unwrap();
+    AppState {
+        repository,
+        ..common::state(|_| {})
+    }
+}
+
+async fn endpoints_report_health(available: Server, unavailable: Server) {
+    for (is_available, path, status, body) in CASES {
+        let server = if *is_available {
+            &available
+        } else {
+            &unavailable
+        };
+        let res = server.send(Request::get(path)).await;
+        assert_eq!(res.status, *status, "{path}");
+        assert!(res.text().starts_with(body), "{path}: {}", res.text());
+    }
+}
+
+#[cfg(feature = "axum")]
+mod axum_server {
+    use super::*;
+
+    #[tokio::test]
+    async fn endpoints_report_health() {
+        super::endpoints_report_health(
+            Server::axum(common::state(|_| {})),
+            Server::axum(unavailable()),
+        )
+        .await;
+    }
+}
+
+#[cfg(feature = "actix")]
+mod actix_server {
+    use super::*;
+
+    #[actix_web::test]
+    async fn endpoints_report_health() {
+        super::endpoints_report_health(
+            Server::actix(common::state(|_| {})).await,
+            Server::actix(unavailable()).await,
+        )
+        .await;
+    }
+}
@@ -0,0 +1,76 @@
+#![cfg(any(feature = "axum", feature = "actix"))]
+
+mod common;
+
+use common::{Request, Server};
+use leptos_demo_portlet::server::AppState;
+
+const BASE: &str = "https://example.com";
+
+/// Each page and the tags expected in the `<head>` rendered for it.
+const CASES: &[(&str, &[&str])] = &[
+    (
+        "/author/carl/",
+        &[
+            "<title>Carl</title>",
+            r#"<meta name="description" content="Carl and their articles.">"#,
+            r#"<link href="https://example.com/author/carl/" rel="canonical">"#,
+            r#"<meta property="og:type" content="profile">"#,
+            r#"<meta property="og:site_name" content="Reactive Portlets in Leptos">"#,
+            r#"<meta property="og:title" content="Carl">"#,
+            r#"<meta property="og:description" content="Carl and their articles.">"#,
+            r#"<meta property="og:url" content="https://example.com/author/carl/">"#,
+            r#"<meta name="twitter:card" content="summary">"#,
+        ],
+    ),
+    (
+        "/article/8/",
+        &[
+            "<title>A quick summary on...</title>",
+            r#"<meta name="description" content="A quick summary on... by carl.">"#,
+            r#"<link href="https://example.com/article/8/" rel="canonical">"#,
+            r#"<meta property="og:type" content="article">"#,
+            r#"<meta property="og:title" content="A quick summary on...">"#,
+            r#"<meta property="og:description" content="A quick summary on... by carl.">"#,
+            r#"<meta property="og:url" content="https://example.com/article/8/">"#,
+            r#"<meta name="twitter:title" content="A quick summary on...">"#,
+        ],
+    ),
+];
+
+fn state() -> AppState {
+    common::state(|config| config.public_url = format!("{BASE}/"))
+}
+
+async fn pages_describe_themselves(server: Server) {
+    for (path, tags) in CASES {
+        let res = server.send(Request::get(path)).await;
+        assert_eq!(res.status, 200, "{path}");
+        let body = res.text();
+        let head = body.split("</head>").next().unwrap();
+        for tag in *tags {
+            assert!(head.contains(tag), "{path}: {tag} in {head}");
+        }
+        assert_eq!(head.matches("<title>").count(), 1, "{path}: {head}");
+    }
+}
+
+#[cfg(feature = "axum")]
+mod axum_server {
+    use super::*;
+
+    #[tokio::test]
+    async fn pages_describe_themselves() {
+        super::pages_describe_themselves(Server::axum(state())).await;
+    }
+}
+
+#[cfg(feature = "actix")]
+mod actix_server {
+    use super::*;
+
+    #[actix_web::test]
+    async fn pages_describe_themselves() {
+        super::pages_describe_themselves(Server::actix(state()).await).await;
+    }
+}
@@ -0,0 +1,58 @@
+#![cfg(any(feature = "axum", feature = "actix"))]
+
+//! The recorder of the metrics is installed for the whole process, so
+//! these tests have a binary of their own.
+
+mod common;
+
+use common::{Request, Server};
+use leptos::server_fn::ServerFn;
+use leptos_demo_portlet::{auth::CurrentUser, metrics};
+
+/// The series expected once a server function was called and a page
+/// rendered, by the start of their line.
+const SERIES: &[&str] = &[
+    "server_fn_requests_total{function=\"current_user\",outcome=\"ok\"} ",
+    "server_fn_duration_seconds_count{function=\"current_user\"} ",
+    "ssr_render_duration_seconds_count{route=",
+    "waiter_subscribers ",
+];
+
+async fn calls_and_renders_are_recorded(server: Server) {
+    metrics::install();
+    let res = server.send(Request::post(CurrentUser::PATH, "")).await;
+    assert_eq!(res.status, 200, "{}", res.text());
+    let res = server.send(Request::get("/author/carl/")).await;
+    assert_eq!(res.status, 200, "{}", res.text());
+
+    let res = server.send(Request::get("/metrics")).await;
+    assert_eq!(res.status, 200);
+    assert_eq!(res.header("content-type"), Some(metrics::CONTENT_TYPE));
+    let body = res.text();
+    for series in SERIES {
+        assert!(
+            body.lines().any(|line| line.starts_with(series)),
+            "{series}: {body}"
+        );
+    }
+}
+
+#[cfg(feature = "axum")]
+mod axum_server {
+    use super::*;
+
+    #[tokio::test]
+    async fn calls_and_renders_are_recorded() {
+        super::calls_and_renders_are_recorded(Server::axum(common::state(|_| {}))).await;
+    }
+}
+
+#[cfg(feature = "actix")]
+mod actix_server {
+    use super::*;
+
+    #[actix_web::test]
+    async fn calls_and_renders_are_recorded() {
+        super::calls_and_renders_are_recorded(Server::actix(common::state(|_| {})).await).await;
+    }
+}
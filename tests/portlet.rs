@@ -0,0 +1,104 @@
+#![cfg(feature = "ssr")]
+
+use any_spawner::Executor;
+use leptos::prelude::*;
+use leptos_demo_portlet::{error::AppError, portlet::PortletCtx};
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// The interval of the timers of the tests.
+const TICK: Duration = Duration::from_millis(20);
+
+/// A resource counting its fetches in `fetches`.
+fn counted(fetches: &Arc<AtomicUsize>) -> ArcResource<Result<String, AppError>> {
+    let fetches = fetches.clone();
+    ArcResource::new(
+        || (),
+        move |_| {
+            let fetches = fetches.clone();
+            async move { Ok(fetches.fetch_add(1, Ordering::SeqCst).to_string()) }
+        },
+    )
+}
+
+/// The number of fetches once those under way have completed.
+async fn settled(fetches: &AtomicUsize) -> usize {
+    tokio::time::sleep(TICK / 2).await;
+    fetches.load(Ordering::SeqCst)
+}
+
+/// Provide the context of the portlet in a new owner, returning the
+/// owner, which must be kept, and the write signal of the context.
+fn portlet() -> (Owner, WriteSignal<PortletCtx<String>>) {
+    let _ = Executor::init_tokio();
+    let owner = Owner::new_root(None);
+    owner.set();
+    PortletCtx::<String>::provide();
+    (owner, expect_context())
+}
+
+#[tokio::test]
+async fn portlet_refetches_on_its_timer_until_cleaned_up() {
+    let (owner, ws) = portlet();
+    let fetches = Arc::new(AtomicUsize::new(0));
+    let page = owner.child();
+    page.with(|| {
+        ws.update(|ctx| {
+            ctx.set_with_timer(counted(&fetches), |refetch| {
+                let timer = tokio::spawn(async move {
+                    let mut interval = tokio::time::interval(TICK);
+                    interval.tick().await;
+                    loop {
+                        interval.tick().await;
+                        refetch();
+                    }
+                });
+                Some(Arc::new(move || timer.abort()))
+            })
+        })
+    });
+
+    let first = settled(&fetches).await;
+    tokio::time::sleep(TICK * 5).await;
+    let polled = settled(&fetches).await;
+    assert!(polled >= first + 3, "{first} then {polled}");
+
+    // the page setting the portlet is gone
+    page.cleanup();
+    let stopped = settled(&fetches).await;
+    tokio::time::sleep(TICK * 5).await;
+    assert_eq!(settled(&fetches).await, stopped);
+}
+
+#[tokio::test]
+async fn portlet_stops_polling_when_cleared() {
+    let (_owner, ws) = portlet();
+    let fetches = Arc::new(AtomicUsize::new(0));
+    let stopped = Arc::new(AtomicUsize::new(0));
+    ws.update(|ctx| {
+        ctx.set_with_timer(counted(&fetches), |_| {
+            let stopped = stopped.clone();
+            Some(Arc::new(move || {
+                stopped.fetch_add(1, Ordering::SeqCst);
+            }))
+        })
+    });
+    assert_eq!(stopped.load(Ordering::SeqCst), 0);
+    ws.update(|ctx| ctx.clear());
+    assert_eq!(stopped.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn portlet_does_not_poll_during_ssr() {
+    let (_owner, ws) = portlet();
+    let fetches = Arc::new(AtomicUsize::new(0));
+    ws.update(|ctx| ctx.set_with_interval(counted(&fetches), TICK));
+    let first = settled(&fetches).await;
+    tokio::time::sleep(TICK * 5).await;
+    assert_eq!(settled(&fetches).await, first);
+}
@@ -0,0 +1,118 @@
+#![cfg(any(feature = "axum", feature = "actix"))]
+
+mod common;
+
+use common::{Request, Server};
+use leptos::server_fn::ServerFn;
+use leptos_demo_portlet::{
+    auth::{CsrfToken, Login},
+    config::LimitsConfig,
+    server::AppState,
+};
+use std::net::{IpAddr, SocketAddr};
+
+/// The last byte of the client IP and whether the request carries the
+/// session, the server function called with the body, and the status
+/// expected if the request is turned away.  Reads and writes are each
+/// limited to two a minute, and bodies to 64 bytes.
+const CASES: &[(u8, bool, &str, &str, Option<u16>)] = &[
+    (1, false, LOGIN, WRONG, None),
+    (1, false, LOGIN, WRONG, None),
+    (1, false, LOGIN, WRONG, Some(429)),
+    // the limits are per IP
+    (2, false, LOGIN, WRONG, None),
+    // and reads are counted apart from writes
+    (1, false, READ, "", None),
+    // as well as per session, whatever the IP
+    (3, true, READ, "", None),
+    (4, true, READ, "", None),
+    (5, true, READ, "", Some(429)),
+    (6, false, READ, LARGE, Some(413)),
+];
+
+const LOGIN: &str = Login::PATH;
+const READ: &str = CsrfToken::PATH;
+const WRONG: &str = "username=albert&password=wrong";
+const LARGE: &str = "0123456789012345678901234567890123456789012345678901234567890123456789";
+
+/// The last byte of the IP of the trusted proxy, and the `X-Forwarded-For`
+/// of reads through it with whether they are expected to be turned away.
+/// Its clients are limited each on their own, ignoring any addresses they
+/// send themselves.
+const PROXY: u8 = 9;
+const FORWARDED: &[(&str, bool)] = &[
+    ("203.0.113.1", false),
+    ("198.51.100.7, 203.0.113.1", false),
+    ("203.0.113.1", true),
+    ("203.0.113.2", false),
+];
+
+/// The state with the limits of the cases.
+fn state() -> AppState {
+    common::state(|config| {
+        config.limits = LimitsConfig {
+            reads_per_minute: 2,
+            writes_per_minute: 2,
+            max_body_bytes: 64,
+            trusted_proxies: vec![addr(PROXY).ip()],
+        }
+    })
+}
+
+fn addr(ip: u8) -> SocketAddr {
+    SocketAddr::new(IpAddr::from([10, 0, 0, ip]), 50_000)
+}
+
+/// Run the cases, with albert logged in as the session of those that
+/// carry one.
+async fn calls_are_limited(server: Server, cookie: String) {
+    for case @ (ip, with_session, path, body, expected) in CASES {
+        let req = Request::post(path, *body)
+            .peer(addr(*ip))
+            .cookie(with_session.then_some(&cookie));
+        let res = server.send(req).await;
+        match expected {
+            None => assert!(![413, 429].contains(&res.status), "{case:?}: {res:?}"),
+            Some(expected) => assert_eq!(res.status, *expected, "{case:?}"),
+        }
+        if res.status == 429 {
+            let seconds: u64 = res
+                .header("retry-after")
+                .expect("Retry-After")
+                .parse()
+                .unwrap();
+            assert!((1..=30).contains(&seconds), "{case:?}: {seconds}");
+        }
+    }
+
+    for case @ (forwarded_for, limited) in FORWARDED {
+        let req = Request::post(READ, "")
+            .peer(addr(PROXY))
+            .header("x-forwarded-for", *forwarded_for);
+        assert_eq!(server.send(req).await.status == 429, *limited, "{case:?}");
+    }
+}
+
+#[cfg(feature = "axum")]
+mod axum_server {
+    use super::*;
+
+    #[tokio::test]
+    async fn calls_are_limited() {
+        let state = state();
+        let (cookie, _) = common::login(&state, "albert");
+        super::calls_are_limited(Server::axum(state), cookie).await;
+    }
+}
+
+#[cfg(feature = "actix")]
+mod actix_server {
+    use super::*;
+
+    #[actix_web::test]
+    async fn calls_are_limited() {
+        let state = state();
+        let (cookie, _) = common::login(&state, "albert");
+        super::calls_are_limited(Server::actix(state).await, cookie).await;
+    }
+}
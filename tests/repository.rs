@@ -0,0 +1,145 @@
+#![cfg(feature = "ssr")]
+
+mod common;
+
+use common::TempDir;
+use leptos_demo_portlet::{
+    auth::Role,
+    cli::Task,
+    config::{AppConfig, StorageBackend, StorageConfig},
+    repository::{self, Content, RepositoryError, SCHEMA_VERSION},
+};
+use serde_json::{Map, Value};
+use std::path::Path;
+
+/// The demo content as it was stored at the version, before the users
+/// were added in version 2 and given roles in version 3.
+fn document(version: u64) -> Map<String, Value> {
+    let Value::Object(mut document) = serde_json::from_str(&Content::demo().to_json()).unwrap()
+    else {
+        unreachable!()
+    };
+    if version == 0 {
+        document.remove("version");
+    } else {
+        document.insert("version".to_string(), version.into());
+    }
+    if version < 2 {
+        document.remove("users");
+    } else if version < 3 {
+        for user in document["users"].as_object_mut().unwrap().values_mut() {
+            user.as_object_mut().unwrap().remove("role");
+        }
+    }
+    document
+}
+
+fn write(path: &Path, document: &Map<String, Value>) {
+    std::fs::write(path, serde_json::to_string(document).unwrap()).unwrap();
+}
+
+fn json_config(path: &Path) -> AppConfig {
+    AppConfig {
+        storage: StorageConfig {
+            backend: StorageBackend::Json,
+            path: path.to_path_buf(),
+        },
+        ..AppConfig::default()
+    }
+}
+
+#[test]
+fn migrations_upgrade_every_prior_version() {
+    let dir = TempDir::new("migrate");
+    let path = dir.path().join("content.json");
+    for version in 0..SCHEMA_VERSION {
+        write(&path, &document(version));
+        assert!(
+            matches!(Content::load(&path), Err(RepositoryError::Outdated { version: v, .. }) if v == version),
+            "{version}"
+        );
+        assert_eq!(repository::migrate(&path).unwrap(), version);
+        let content = Content::load(&path).unwrap();
+        assert_eq!(content.authors, Content::demo().authors, "{version}");
+        assert_eq!(content.articles.len(), Content::demo().articles.len());
+        if version < 2 {
+            assert!(content.users.is_empty(), "{version}");
+        }
+    }
+
+    // the current version is left alone, and newer ones are refused
+    let current = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(repository::migrate(&path).unwrap(), SCHEMA_VERSION);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), current);
+    write(&path, &document(SCHEMA_VERSION + 1));
+    assert!(matches!(
+        repository::migrate(&path),
+        Err(RepositoryError::UnsupportedVersion { .. })
+    ));
+}
+
+#[test]
+fn migration_gives_users_roles() {
+    let dir = TempDir::new("roles");
+    let path = dir.path().join("content.json");
+    write(&path, &document(2));
+    assert_eq!(repository::migrate(&path).unwrap(), 2);
+    let content = Content::load(&path).unwrap();
+    let roles: Vec<_> = content
+        .users
+        .iter()
+        .map(|(username, user)| (username.as_str(), user.role))
+        .collect();
+    // users named after an author write as them, and the rest only read
+    assert_eq!(
+        roles,
+        [
+            ("albert", Role::Author),
+            ("bethany", Role::Author),
+            ("carl", Role::Author),
+            ("dorothy", Role::Author),
+            ("erin", Role::Reader),
+        ]
+    );
+    for (username, user) in &content.users {
+        assert_eq!(
+            user.password_hash,
+            Content::demo().users[username].password_hash,
+            "{username}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn seed_and_export() {
+    let dir = TempDir::new("seed");
+    let path = dir.path().join("content.json");
+    let config = json_config(&path);
+    let seed = |fixture, force| Task::Seed { fixture, force };
+
+    seed(None, false).run(config.clone()).await.unwrap();
+    assert_eq!(
+        Content::load(&path).unwrap().to_json(),
+        Content::demo().to_json()
+    );
+    assert!(seed(None, false).run(config.clone()).await.is_err());
+
+    // fixtures of prior versions are migrated as they are read
+    let fixture = dir.path().join("fixture.json");
+    let mut old = document(1);
+    old["authors"].as_object_mut().unwrap().remove("albert");
+    write(&fixture, &old);
+    seed(Some(fixture), true).run(config.clone()).await.unwrap();
+    let content = Content::load(&path).unwrap();
+    assert!(!content.authors.contains_key("albert"));
+    assert!(content.users.is_empty());
+
+    let output = dir.path().join("export.json");
+    Task::Export {
+        output: Some(output.clone()),
+    }
+    .run(config)
+    .await
+    .unwrap();
+    assert_eq!(Content::load(&output).unwrap().to_json(), content.to_json());
+}
@@ -0,0 +1,84 @@
+#![cfg(any(feature = "axum", feature = "actix"))]
+
+mod common;
+
+use common::{Request, Response, Server};
+
+/// A page, whose policy must allow its scripts by nonce, and a response
+/// from outside the app, which must carry the other headers all the same.
+const PAGE: &str = "/author/bethany/";
+const API: &str = "/api/v1/authors";
+
+/// The headers expected on every response.
+const HEADERS: &[(&str, &str)] = &[
+    ("x-content-type-options", "nosniff"),
+    ("referrer-policy", "strict-origin-when-cross-origin"),
+    (
+        "strict-transport-security",
+        "max-age=31536000; includeSubDomains",
+    ),
+];
+
+/// Check the headers of the page and return the nonce of its policy
+/// after making sure each of its scripts carries it.
+fn check_page(res: &Response) -> String {
+    let body = res.text();
+    for (name, value) in HEADERS {
+        assert_eq!(res.header(name), Some(*value), "{name}");
+    }
+    let policy = res
+        .header("content-security-policy")
+        .expect("content-security-policy");
+    let nonce = policy
+        .split("'nonce-")
+        .nth(1)
+        .and_then(|rest| rest.split('\'').next())
+        .expect("nonce in policy")
+        .to_string();
+    let scripts = body.matches("<script").count();
+    assert!(scripts > 0, "{body}");
+    assert_eq!(
+        body.matches(&format!("<script nonce=\"{nonce}\"")).count()
+            + body
+                .matches(&format!("<script type=\"module\" nonce=\"{nonce}\""))
+                .count(),
+        scripts,
+        "{body}"
+    );
+    nonce
+}
+
+fn check_api(res: &Response) {
+    for (name, value) in HEADERS {
+        assert_eq!(res.header(name), Some(*value), "{name}");
+    }
+    assert_eq!(res.header("content-security-policy"), None);
+}
+
+async fn security_headers_are_sent(server: Server) {
+    let first = check_page(&server.send(Request::get(PAGE)).await);
+    let second = check_page(&server.send(Request::get(PAGE)).await);
+    assert_ne!(first, second);
+
+    check_api(&server.send(Request::get(API)).await);
+}
+
+#[cfg(feature = "axum")]
+mod axum_server {
+    use super::*;
+
+    #[tokio::test]
+    async fn security_headers_are_sent() {
+        super::security_headers_are_sent(Server::axum(common::state(|_| {}))).await;
+    }
+}
+
+#[cfg(feature = "actix")]
+mod actix_server {
+    use super::*;
+
+    #[actix_web::test]
+    async fn security_headers_are_sent() {
+        super::security_headers_are_sent(Server::actix(common::state(|_| {})).await).await;
+    }
+}
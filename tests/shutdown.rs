@@ -0,0 +1,124 @@
+#![cfg(any(feature = "axum", feature = "actix"))]
+
+//! Shutdown is requested of the whole process, so this is the only test
+//! of its binary.
+
+mod common;
+
+use leptos_demo_portlet::events;
+use std::{
+    io::{Read, Write},
+    net::{SocketAddr, TcpStream},
+    time::Duration,
+};
+
+/// How long to wait for the stream to open and to end.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Open the event stream of the server, returning the connection and the
+/// bytes read up to the end of the response headers and past them.
+fn open(addr: SocketAddr) -> (TcpStream, String) {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.set_read_timeout(Some(TIMEOUT)).unwrap();
+    write!(
+        stream,
+        "GET {} HTTP/1.1\r\nhost: {addr}\r\n\r\n",
+        events::PATH
+    )
+    .unwrap();
+    let mut read = Vec::new();
+    let mut buf = [0; 1024];
+    while !read.windows(4).any(|window| window == b"\r\n\r\n") {
+        let n = stream.read(&mut buf).expect("no response headers");
+        assert!(n > 0, "connection closed before the response headers");
+        read.extend_from_slice(&buf[..n]);
+    }
+    (stream, String::from_utf8_lossy(&read).into_owned())
+}
+
+/// The rest of the response, read until the server closes the connection.
+fn rest(mut stream: TcpStream) -> String {
+    let mut rest = Vec::new();
+    stream
+        .read_to_end(&mut rest)
+        .expect("the event stream did not end");
+    String::from_utf8_lossy(&rest).into_owned()
+}
+
+/// Check the event stream opened, stop the server with `stop` and check
+/// the stream then ended.
+async fn stream_ends_on_shutdown(addr: SocketAddr, stop: impl FnOnce()) {
+    let (stream, head) = tokio::task::spawn_blocking(move || open(addr))
+        .await
+        .unwrap();
+    assert!(head.starts_with("HTTP/1.1 200"), "{head}");
+    assert!(
+        head.to_ascii_lowercase()
+            .contains(&format!("content-type: {}", events::CONTENT_TYPE)),
+        "{head}"
+    );
+    stop();
+    let rest = tokio::task::spawn_blocking(move || rest(stream))
+        .await
+        .unwrap();
+    // the last chunk of the body, ending it
+    assert!(format!("{head}{rest}").ends_with("0\r\n\r\n"), "{rest}");
+}
+
+#[cfg(feature = "axum")]
+mod axum_server {
+    use super::*;
+    use leptos_demo_portlet::server;
+    use tokio::sync::oneshot;
+
+    #[tokio::test]
+    async fn stream_ends_on_shutdown() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (tx, rx) = oneshot::channel();
+        let served = tokio::spawn(server::axum::serve(
+            listener,
+            server::axum::router(common::state(|_| {})),
+            TIMEOUT,
+            async move {
+                let _ = rx.await;
+            },
+        ));
+        super::stream_ends_on_shutdown(addr, move || tx.send(()).unwrap()).await;
+        tokio::time::timeout(TIMEOUT, served)
+            .await
+            .expect("the server did not stop")
+            .unwrap()
+            .unwrap();
+    }
+}
+
+#[cfg(feature = "actix")]
+mod actix_server {
+    use super::*;
+    use actix_web::HttpServer;
+    use leptos_demo_portlet::server;
+    use tokio::sync::oneshot;
+
+    #[actix_web::test]
+    async fn stream_ends_on_shutdown() {
+        let state = common::state(|_| {});
+        let server = HttpServer::new(move || server::actix::app(state.clone()))
+            .workers(1)
+            .disable_signals()
+            .shutdown_timeout(TIMEOUT.as_secs())
+            .bind("127.0.0.1:0")
+            .unwrap();
+        let addr = server.addrs()[0];
+        let (tx, rx) = oneshot::channel();
+        let served = actix_web::rt::spawn(server::actix::serve(server.run(), async move {
+            let _ = rx.await;
+        }));
+        super::stream_ends_on_shutdown(addr, move || tx.send(()).unwrap()).await;
+        tokio::time::timeout(TIMEOUT, served)
+            .await
+            .expect("the server did not stop")
+            .unwrap()
+            .unwrap();
+    }
+}
@@ -0,0 +1,94 @@
+#![cfg(any(feature = "axum", feature = "actix"))]
+
+mod common;
+
+use common::{Request, Server};
+use leptos_demo_portlet::server::{sitemap, AppState};
+use std::collections::BTreeSet;
+
+const BASE: &str = "https://example.com";
+
+const AUTHORS: &[&str] = &["albert", "bethany", "carl", "dorothy"];
+
+/// The paths of the routes left out, as they are of no use to crawlers.
+const EXCLUDED: &[&str] = &["/login", "/logout", "/new", "/edit"];
+
+const ROBOTS: &str = "User-agent: *\nDisallow: /api/\nDisallow: /metrics\nAllow: /\n\n\
+    Sitemap: https://example.com/sitemap.xml\n";
+
+fn state() -> AppState {
+    common::state(|config| config.public_url = format!("{BASE}/"))
+}
+
+/// Every page of the demo content.
+fn expected() -> BTreeSet<String> {
+    let mut paths = vec![
+        "/".to_string(),
+        "/author/".to_string(),
+        "/article/".to_string(),
+    ];
+    for name in AUTHORS {
+        paths.push(format!("/author/{name}/"));
+        paths.push(format!("/author/{name}/articles"));
+    }
+    for id in 1..=10 {
+        paths.push(format!("/article/{id}/"));
+        paths.push(format!("/article/{id}/comments"));
+        paths.push(format!("/article/{id}/history"));
+    }
+    paths
+        .into_iter()
+        .map(|path| format!("{BASE}{path}"))
+        .collect()
+}
+
+async fn sitemap_lists_every_page(server: Server) {
+    let res = server.send(Request::get("/sitemap.xml")).await;
+    assert_eq!(res.status, 200);
+    assert_eq!(res.header("content-type"), Some(sitemap::CONTENT_TYPE));
+    let body = res.text();
+    assert!(
+        body.contains(r#"<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">"#),
+        "{body}"
+    );
+    let locs = body
+        .split("<loc>")
+        .skip(1)
+        .map(|url| url.split("</loc>").next().unwrap().to_string())
+        .collect::<BTreeSet<_>>();
+    for loc in &locs {
+        assert!(
+            !EXCLUDED.iter().any(|excluded| loc.ends_with(excluded)),
+            "{loc}"
+        );
+    }
+    assert_eq!(locs, expected());
+
+    let res = server.send(Request::get("/robots.txt")).await;
+    assert_eq!(res.status, 200);
+    assert_eq!(
+        res.header("content-type"),
+        Some(sitemap::ROBOTS_CONTENT_TYPE)
+    );
+    assert_eq!(res.text(), ROBOTS);
+}
+
+#[cfg(feature = "axum")]
+mod axum_server {
+    use super::*;
+
+    #[tokio::test]
+    async fn sitemap_lists_every_page() {
+        super::sitemap_lists_every_page(Server::axum(state())).await;
+    }
+}
+
+#[cfg(feature = "actix")]
+mod actix_server {
+    use super::*;
+
+    #[actix_web::test]
+    async fn sitemap_lists_every_page() {
+        super::sitemap_lists_every_page(Server::actix(state()).await).await;
+    }
+}
@@ -0,0 +1,67 @@
+#![cfg(any(feature = "axum", feature = "actix"))]
+
+mod common;
+
+use common::TempDir;
+use leptos_demo_portlet::{
+    server::{sitemap, static_site, AppState},
+    static_site::MARKER,
+};
+use std::fs;
+
+/// A site root holding an asset, which must be copied along with the pages.
+fn state(site_root: &TempDir) -> AppState {
+    fs::create_dir_all(site_root.path().join("pkg")).unwrap();
+    fs::write(site_root.path().join("pkg/app.css"), "body {}").unwrap();
+    let mut state = common::state(|_| {});
+    state.leptos_options.site_root = site_root.path().to_string_lossy().into();
+    state
+}
+
+async fn every_page_is_exported(state: AppState) {
+    let output = TempDir::new("static-output");
+    let paths = sitemap::paths(&state.repository).unwrap();
+    let exported = static_site::export(state, output.path()).await.unwrap();
+    assert_eq!(exported, paths.len());
+
+    for path in &paths {
+        let file = output
+            .path()
+            .join(path.trim_matches('/'))
+            .join("index.html");
+        let html = fs::read_to_string(&file).unwrap_or_else(|err| panic!("{path}: {err}"));
+        assert!(
+            html.contains(&format!(r#"<meta name="{MARKER}" content="true"/>"#)),
+            "{path}"
+        );
+    }
+    // the listings are not paginated, as the query string would be ignored
+    let articles = fs::read_to_string(output.path().join("article/index.html")).unwrap();
+    assert!(articles.contains("Page 1 of 1"), "{articles}");
+    assert_eq!(
+        fs::read_to_string(output.path().join("pkg/app.css")).unwrap(),
+        "body {}"
+    );
+}
+
+#[cfg(feature = "axum")]
+mod axum_server {
+    use super::*;
+
+    #[tokio::test]
+    async fn every_page_is_exported() {
+        let site_root = TempDir::new("static-site");
+        super::every_page_is_exported(state(&site_root)).await;
+    }
+}
+
+#[cfg(feature = "actix")]
+mod actix_server {
+    use super::*;
+
+    #[actix_web::test]
+    async fn every_page_is_exported() {
+        let site_root = TempDir::new("static-site");
+        super::every_page_is_exported(state(&site_root)).await;
+    }
+}